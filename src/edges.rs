@@ -0,0 +1,73 @@
+use failure::Error;
+use serde_json;
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use movie::Movie;
+use tmdb::TMDB;
+
+/// One line of an edge list: [source] links to [target] at position [rank]
+#[derive(Serialize)]
+pub struct Edge<'a> {
+    pub source: &'a str,
+    pub target: &'a str,
+    pub rank: usize,
+}
+
+/// Writes `edges/similar.jsonl` and `edges/recommended.jsonl` with up to [count] edges
+/// per saved movie, keyed by IMDB ID, and returns the number of edges written.
+///
+/// Targets outside the crawl are looked up on TMDB; those without an IMDB ID are dropped.
+pub fn build(tmdb: &TMDB, count: usize) -> Result<usize, Error> {
+    let movies = Movie::saved()?
+        .filter(|movie| movie.tmdb_id != 0)
+        .map(|movie| (movie.tmdb_id, movie.id))
+        .collect::<Vec<_>>();
+
+    let mut imdb_ids = movies.iter()
+        .map(|&(tmdb_id, ref imdb_id)| (tmdb_id, Some(imdb_id.clone())))
+        .collect::<HashMap<_, _>>();
+
+    fs::create_dir_all("edges")?;
+    let mut similar = BufWriter::new(File::create("edges/similar.jsonl")?);
+    let mut recommended = BufWriter::new(File::create("edges/recommended.jsonl")?);
+    let mut total = 0;
+
+    for &(tmdb_id, ref source) in &movies {
+        let lists = [
+            (tmdb.get_similar(tmdb_id, count), &mut similar),
+            (tmdb.get_recommendations(tmdb_id, count), &mut recommended),
+        ];
+
+        for (targets, file) in lists {
+            let targets = match targets {
+            | Ok(targets) => targets,
+            | Err(err) => {
+                warn!("[EDGES] {}: {}", source, err);
+                continue
+            }
+            };
+
+            for (rank, target) in targets.into_iter().enumerate() {
+                let imdb_id = imdb_ids.entry(target).or_insert_with(|| {
+                    tmdb.get_imdb_id(target)
+                        .map_err(|err| warn!("[EDGES] no IMDB ID for {}: {}", target, err))
+                        .unwrap_or(None)
+                });
+
+                if let Some(ref target) = *imdb_id {
+                    let edge = Edge { source, target, rank };
+                    file.write_all(serde_json::to_string(&edge)?.as_bytes())?;
+                    file.write_all(b"\n")?;
+                    total += 1;
+                }
+            }
+        }
+    }
+
+    similar.flush()?;
+    recommended.flush()?;
+    Ok(total)
+}
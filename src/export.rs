@@ -0,0 +1,61 @@
+use failure::Error;
+use flate2::read::GzDecoder;
+use serde_json::from_str;
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// One line of TMDB's daily ID export, `movie_ids_MM_DD_YYYY.json.gz`
+#[derive(Deserialize)]
+pub struct ExportEntry {
+    pub id: i32,
+    pub original_title: String,
+    pub adult: bool,
+    pub video: bool,
+    pub popularity: f32,
+}
+
+/// Decides which exported movies are worth crawling.
+pub struct ExportFilter {
+    /// Keep adult movies
+    pub adult: bool,
+    /// Keep video releases, e.g. recorded concerts and direct-to-video compilations
+    pub video: bool,
+    /// Drop movies less popular than this
+    pub min_popularity: f32,
+}
+
+impl Default for ExportFilter {
+    fn default() -> Self {
+        ExportFilter {
+            adult: false,
+            video: false,
+            min_popularity: 0.0,
+        }
+    }
+}
+
+impl ExportFilter {
+    pub fn accepts(&self, entry: &ExportEntry) -> bool {
+        (self.adult || !entry.adult)
+            && (self.video || !entry.video)
+            && entry.popularity >= self.min_popularity
+    }
+}
+
+/// Returns the IDs in gzipped export file [path] that pass [filter], most popular first.
+pub fn load<P: AsRef<Path>>(path: P, filter: &ExportFilter) -> Result<Vec<i32>, Error> {
+    let reader = BufReader::new(GzDecoder::new(File::open(path)?));
+    let mut entries = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() { continue }
+        let entry = from_str::<ExportEntry>(&line)?;
+        if filter.accepts(&entry) { entries.push(entry) }
+    }
+
+    entries.sort_by(|a, b| b.popularity.partial_cmp(&a.popularity).unwrap_or(Ordering::Equal));
+    Ok(entries.into_iter().map(|entry| entry.id).collect())
+}
@@ -0,0 +1,188 @@
+use failure::Error;
+use reqwest;
+use reqwest::header::{Headers, RetryAfter};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Fail)]
+pub enum FetchError {
+    #[fail(display = "server responded with HTTP {}", status)]
+    Status { status: u16 },
+}
+
+/// Raw HTTP response, independent of the transport that produced it.
+pub struct Response {
+    pub status: u16,
+    /// Delay requested by the server's `Retry-After` header
+    pub retry_after: Option<Duration>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        self.status >= 200 && self.status < 300
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Transport used for every outgoing request made by [TMDB], [IMDB] and poster downloads.
+pub trait Fetcher: Send + Sync {
+    fn fetch(&self, url: &str) -> Result<Response, Error>;
+
+    /// Fetches [url] sending extra request [headers]; canned transports ignore them.
+    fn fetch_with_headers(&self, url: &str, _headers: &[(&str, &str)]) -> Result<Response, Error> {
+        self.fetch(url)
+    }
+}
+
+/// Fetches over the network with a shared [reqwest::Client].
+pub struct Reqwest {
+    client: reqwest::Client,
+}
+
+impl Default for Reqwest {
+    fn default() -> Self {
+        Reqwest { client: reqwest::Client::new() }
+    }
+}
+
+impl Fetcher for Reqwest {
+    fn fetch(&self, url: &str) -> Result<Response, Error> {
+        self.fetch_with_headers(url, &[])
+    }
+
+    fn fetch_with_headers(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, Error> {
+        let mut raw = Headers::new();
+        for &(name, value) in headers {
+            raw.set_raw(name.to_owned(), value.to_owned());
+        }
+
+        let mut response = self.client.get(url).headers(raw).send()?;
+        let mut body = Vec::new();
+        response.copy_to(&mut body)?;
+
+        let retry_after = response.headers()
+            .get::<RetryAfter>()
+            .map(|header| match *header {
+            | RetryAfter::Delay(delay) => delay,
+            | RetryAfter::DateTime(date) => SystemTime::from(date)
+                .duration_since(SystemTime::now())
+                .unwrap_or_default(),
+            });
+
+        Ok(Response { status: response.status().as_u16(), retry_after, body })
+    }
+}
+
+/// Serves canned responses from memory; unknown URLs are 404s.
+#[derive(Default)]
+pub struct Fixture {
+    pages: HashMap<String, (u16, Vec<u8>)>,
+}
+
+impl Fixture {
+    pub fn insert<B: Into<Vec<u8>>>(&mut self, url: &str, body: B) -> &mut Self {
+        self.insert_status(url, 200, body)
+    }
+
+    pub fn insert_status<B: Into<Vec<u8>>>(&mut self, url: &str, status: u16, body: B) -> &mut Self {
+        self.pages.insert(url.to_owned(), (status, body.into()));
+        self
+    }
+}
+
+impl Fetcher for Fixture {
+    fn fetch(&self, url: &str) -> Result<Response, Error> {
+        Ok(
+            self.pages.get(url)
+                .map(|&(status, ref body)| Response { status, retry_after: None, body: body.clone() })
+                .unwrap_or(Response { status: 404, retry_after: None, body: Vec::new() })
+        )
+    }
+}
+
+/// Serves responses from files under [root], named by [Directory::path]; missing files are 404s.
+pub struct Directory {
+    root: PathBuf,
+}
+
+impl Directory {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Directory { root: root.into() }
+    }
+
+    /// Returns the file backing [url]: the scheme and `api_key` parameter are dropped,
+    /// and every character outside `[A-Za-z0-9._-]` becomes `_`.
+    pub fn path(&self, url: &str) -> PathBuf {
+        let url = url.splitn(2, "://").last().unwrap_or(url);
+        let (path, query) = match url.find('?') {
+        | None => (url, ""),
+        | Some(i) => (&url[..i], &url[i + 1..]),
+        };
+
+        let query = query.split('&')
+            .filter(|param| !param.is_empty() && !param.starts_with("api_key="))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let name = if query.is_empty() { path.to_owned() } else { format!("{}?{}", path, query) };
+
+        self.root.join(
+            name.chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+                .collect::<String>()
+        )
+    }
+}
+
+impl Fetcher for Directory {
+    fn fetch(&self, url: &str) -> Result<Response, Error> {
+        let mut body = Vec::new();
+        match File::open(self.path(url)) {
+        | Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(Response { status: 404, retry_after: None, body }),
+        | Err(err) => Err(err)?,
+        | Ok(mut file) => {
+            file.read_to_end(&mut body)?;
+            Ok(Response { status: 200, retry_after: None, body })
+        }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directory_path() {
+        let directory = Directory::new("pages");
+        assert_eq!(
+            directory.path("http://www.imdb.com/title/tt0111161/"),
+            PathBuf::from("pages/www.imdb.com_title_tt0111161_"),
+        );
+        assert_eq!(
+            directory.path("https://api.themoviedb.org/3/movie/278?language=en-US&api_key=KEY"),
+            PathBuf::from("pages/api.themoviedb.org_3_movie_278_language_en-US"),
+        );
+        assert_eq!(
+            directory.path("https://api.themoviedb.org/3/movie/popular?api_key=KEY&page=2"),
+            PathBuf::from("pages/api.themoviedb.org_3_movie_popular_page_2"),
+        );
+    }
+
+    #[test]
+    fn fixture_misses_are_404s() {
+        let mut fixture = Fixture::default();
+        fixture.insert_status("http://a.test/gone", 410, "gone");
+
+        let response = fixture.fetch("http://a.test/gone").unwrap();
+        assert_eq!((response.status, response.text()), (410, "gone".to_owned()));
+        assert_eq!(fixture.fetch("http://a.test/missing").unwrap().status, 404);
+    }
+}
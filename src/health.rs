@@ -0,0 +1,160 @@
+use failure::Error;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use imdb::{Extractor, IMDB};
+use movie::Settings;
+
+/// Well-known titles checked live when no IMDB IDs are given
+pub const SAMPLE: [&str; 5] = ["tt0111161", "tt0068646", "tt0468569", "tt0110912", "tt0137523"];
+
+lazy_static! {
+    /// Name [Directory::path] gives a saved title home page
+    static ref HOME_PAGE: Regex = Regex::new(r"_title_(tt\d+)_$").unwrap();
+}
+
+/// Results of every extractor on one title: the source that matched, or why it failed
+pub struct Row {
+    pub id: String,
+    pub results: Vec<Result<String, String>>,
+}
+
+/// Per-field pass/fail matrix built by [check]
+pub struct Report {
+    pub rows: Vec<Row>,
+}
+
+/// Runs every [Extractor] on each title in [ids], fetched with the transport,
+/// site root and selector profile in [settings].
+pub fn check(settings: &Settings, ids: &[String]) -> Report {
+    let rows = ids.iter()
+        .map(|id| {
+            let results = match IMDB::new(settings, id, id) {
+            | Ok(imdb) => Extractor::ALL.iter()
+                .map(|&extractor| imdb.probe(extractor).map_err(|err| err.to_string()))
+                .collect(),
+            | Err(err) => Extractor::ALL.iter().map(|_| Err(err.to_string())).collect(),
+            };
+            Row { id: id.clone(), results }
+        })
+        .collect();
+
+    Report { rows }
+}
+
+/// Returns the IMDB IDs of every title home page saved in [dir].
+pub fn saved_ids<P: AsRef<Path>>(dir: P) -> Result<Vec<String>, Error> {
+    let mut ids = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        if let Some(id) = name.to_str().and_then(|name| HOME_PAGE.captures(name)) {
+            ids.push(id[1].to_owned());
+        }
+    }
+    ids.sort();
+    Ok(ids)
+}
+
+impl Report {
+    /// Fraction of titles on which the [index]th extractor succeeded
+    pub fn rate(&self, index: usize) -> f64 {
+        if self.rows.is_empty() { return 0.0 }
+        let passed = self.rows.iter().filter(|row| row.results[index].is_ok()).count();
+        passed as f64 / self.rows.len() as f64
+    }
+
+    /// Returns the required fields that succeeded on fewer than [threshold] of the titles.
+    pub fn failing(&self, threshold: f64) -> Vec<&'static str> {
+        Extractor::ALL.iter()
+            .enumerate()
+            .filter(|&(index, extractor)| extractor.required() && self.rate(index) < threshold)
+            .map(|(_, extractor)| extractor.name())
+            .collect()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:<12}", "title")?;
+        for extractor in &Extractor::ALL {
+            write!(f, "{:<12}", extractor.name())?;
+        }
+        writeln!(f)?;
+
+        for row in &self.rows {
+            write!(f, "{:<12}", row.id)?;
+            for result in &row.results {
+                write!(f, "{:<12}", if result.is_ok() { "pass" } else { "FAIL" })?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{:<12}{:<10}{:<10}matched by", "field", "required", "passed")?;
+        for (index, extractor) in Extractor::ALL.iter().enumerate() {
+            let mut sources = BTreeMap::new();
+            for row in &self.rows {
+                if let Ok(ref source) = row.results[index] {
+                    *sources.entry(source.as_str()).or_insert(0) += 1;
+                }
+            }
+
+            writeln!(
+                f,
+                "{:<12}{:<10}{:<10}{}",
+                extractor.name(),
+                if extractor.required() { "yes" } else { "no" },
+                format!("{:.0}%", self.rate(index) * 100.0),
+                sources.iter()
+                    .map(|(source, count)| format!("{} ({})", source, count))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fetch::Directory;
+
+    #[test]
+    fn saved_home_pages_are_recognized() {
+        let directory = Directory::new("");
+        let name = |url: &str| directory.path(url).to_string_lossy().into_owned();
+
+        let home = HOME_PAGE.captures(&name("http://www.imdb.com/title/tt0111161/")).map(|id| id[1].to_owned());
+        assert_eq!(home, Some("tt0111161".to_owned()));
+        assert!(!HOME_PAGE.is_match(&name("http://www.imdb.com/title/tt0111161/criticreviews?ref_=tt_ov_rt")));
+    }
+
+    fn row(passed: &[bool]) -> Row {
+        Row {
+            id: "tt0000001".to_owned(),
+            results: passed.iter()
+                .map(|&passed| if passed { Ok("json-ld".to_owned()) } else { Err("missing".to_owned()) })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn failing_required_fields() {
+        // poster, rating, imdb_score, meta_score, summary, synopsis
+        let report = Report {
+            rows: vec![
+                row(&[true, false, true, false, true, false]),
+                row(&[false, false, false, false, false, false]),
+            ],
+        };
+        assert_eq!(report.rate(0), 0.5);
+        assert_eq!(report.rate(2), 0.5);
+        assert_eq!(report.failing(0.5), Vec::<&str>::new());
+        assert_eq!(report.failing(0.9), vec!["imdb_score"]);
+        assert_eq!(Report { rows: Vec::new() }.failing(0.9), vec!["imdb_score"]);
+    }
+}
@@ -1,24 +1,33 @@
-use reqwest;
 use failure::Error;
+use serde_json;
+use serde_json::Value;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use regex::Regex;
 use regex::Captures;
 use scraper::Html;
-use scraper::Selector;
 
-/// Returns the website of movie with IMDB ID $id
+use fetch::Fetcher;
+use movie::Settings;
+use profile::{Field, Profile};
+use retry::Backoff;
+
+/// Root of the live IMDB website
+pub const IMDB_BASE: &str = "http://www.imdb.com";
+
+/// Returns the website of movie with IMDB ID $id under site root $base
 macro_rules! home_url {
-    ( $id:expr ) => ( format!("http://www.imdb.com/title/{}/", $id) )
+    ( $base:expr, $id:expr ) => ( format!("{}/title/{}/", $base, $id) )
 }
 
-/// Returns the absolute path of IMDB relative path $path
+/// Returns the absolute path of IMDB relative path $path under site root $base
 macro_rules! abs_url {
-    ( $path:expr ) => ( format!("http://www.imdb.com{}", $path) )
+    ( $base:expr, $path:expr ) => ( format!("{}{}", $base, $path) )
 }
 
 macro_rules! metacritic_url {
-    ( $id:expr ) => ( format!("http://www.imdb.com/title/{}/criticreviews?ref_=tt_ov_rt", $id) )
+    ( $base:expr, $id:expr ) => ( format!("{}/title/{}/criticreviews?ref_=tt_ov_rt", $base, $id) )
 }
 
 #[derive(Debug, Fail)]
@@ -48,141 +57,318 @@ pub enum IMDBError {
     Synopsis { name: String },
 }
 
-lazy_static! {
-    static ref POSTER: Selector = Selector::parse(".poster a[href]").unwrap();
-    static ref IMAGE: Selector = Selector::parse("meta[property=\"og:image\"][content]").unwrap();
-    static ref SUMMARY: Selector = Selector::parse("#titleStoryLine [itemprop=description] p").unwrap();
-    static ref SYNOPSIS: Selector = Selector::parse("#titleStoryLine .see-more a[href]").unwrap();
-    static ref TEXT: Selector = Selector::parse("#plot-synopsis-content .ipl-zebra-list__item").unwrap();
-    static ref RATING: Selector = Selector::parse("meta[itemprop=contentRating][content]").unwrap();
+/// schema.org types of the JSON-LD block describing the title itself
+const TITLE_TYPES: [&str; 3] = ["Movie", "TVSeries", "CreativeWork"];
+
+/// Structured data embedded in the home page as schema.org JSON-LD
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct LinkedData {
+    /// Either a type name or a list of them
+    #[serde(rename = "@type")]
+    kind: Option<Value>,
+    /// Either a URL or an `ImageObject` with a `url`
+    image: Option<Value>,
+    description: Option<String>,
+    content_rating: Option<String>,
+    aggregate_rating: Option<AggregateRating>,
+}
+
+/// Numbers may be sent as JSON numbers or as strings
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AggregateRating {
+    rating_value: Option<Value>,
+    rating_count: Option<Value>,
+}
+
+impl LinkedData {
+    /// Returns the first structured data of [home] found by [field] that describes
+    /// the title, or nothing if it has none
+    fn parse(home: &Html, field: &Field) -> Self {
+        field
+            .find(home, |element| {
+                serde_json::from_str::<LinkedData>(&element.text().collect::<String>())
+                    .ok()
+                    .filter(LinkedData::describes_title)
+            })
+            .map(|(data, _)| data)
+            .unwrap_or_default()
+    }
+
+    fn describes_title(&self) -> bool {
+        let is_title = |kind: &Value| kind.as_str().is_some_and(|kind| TITLE_TYPES.contains(&kind));
+        match self.kind {
+        | Some(Value::Array(ref kinds)) => kinds.iter().any(is_title),
+        | Some(ref kind) => is_title(kind),
+        | None => false,
+        }
+    }
+
+    fn poster(&self, resize: &Regex) -> Option<String> {
+        let image = self.image.as_ref()?;
+        image.as_str()
+            .or_else(|| image.get("url").and_then(Value::as_str))
+            .filter(|link| !link.is_empty())
+            .map(|link| resize.replace_all(link, r"@._V1_.jpg").to_string())
+    }
+
+    fn rating(&self) -> Option<String> {
+        self.content_rating.clone().filter(|rating| !rating.is_empty())
+    }
+
+    fn score(&self) -> Option<(f32, i32)> {
+        let rating = self.aggregate_rating.as_ref()?;
+        let value = number(rating.rating_value.as_ref()?)?;
+        let count = number(rating.rating_count.as_ref()?)?;
+        Some((value as f32, count as i32))
+    }
+
+    fn summary(&self) -> Option<String> {
+        self.description.as_ref()
+            .map(|text| unescape(text.trim()))
+            .filter(|text| !text.is_empty())
+    }
+}
+
+/// Reads a JSON number, or a string holding one such as `"1,234"`
+fn number(value: &Value) -> Option<f64> {
+    value.as_f64().or_else(|| value.as_str().and_then(|text| text.replace(",", "").parse().ok()))
+}
 
-    static ref SCORE_VALUE: Selector = Selector::parse("span[itemprop=ratingValue]").unwrap();
-    static ref SCORE_COUNT: Selector = Selector::parse("span[itemprop=ratingCount]").unwrap();
+/// Decodes the HTML entities IMDB leaves in JSON-LD text
+fn unescape(text: &str) -> String {
+    text.replace("&apos;", "'")
+        .replace("&#39;", "'")
+        .replace("&quot;", "\"")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
 
-    static ref HYPERLINK: Regex = Regex::new(r"(\(\s*)?<a[^>]*>([^<]*)</a>(?:\s*\)\s*)?").unwrap();
-    static ref WRITTEN_BY: Regex = Regex::new(r"(?s:\s*<em.*>\s*)").unwrap();
-    static ref RESIZE: Regex = Regex::new(r"@\._V1_.*\.jpg").unwrap();
+/// Source reported by [IMDB::probe] for values read from JSON-LD
+const LINKED_DATA: &str = "json-ld";
+
+/// One value [IMDB] scrapes, as checked by [IMDB::probe]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Extractor {
+    Poster,
+    Rating,
+    IMDBScore,
+    MetacriticScore,
+    Summary,
+    Synopsis,
+}
+
+impl Extractor {
+    pub const ALL: [Extractor; 6] = [
+        Extractor::Poster,
+        Extractor::Rating,
+        Extractor::IMDBScore,
+        Extractor::MetacriticScore,
+        Extractor::Summary,
+        Extractor::Synopsis,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+        | Extractor::Poster => "poster",
+        | Extractor::Rating => "rating",
+        | Extractor::IMDBScore => "imdb_score",
+        | Extractor::MetacriticScore => "meta_score",
+        | Extractor::Summary => "summary",
+        | Extractor::Synopsis => "synopsis",
+        }
+    }
+
+    /// Whether a crawl fails without this value; posters and summaries fall back to TMDB
+    pub fn required(self) -> bool {
+        match self {
+        | Extractor::IMDBScore => true,
+        | Extractor::Poster | Extractor::Rating | Extractor::MetacriticScore | Extractor::Summary | Extractor::Synopsis => false,
+        }
+    }
 }
 
 pub struct IMDB {
+    fetcher: Arc<dyn Fetcher>,
+    retry: Backoff,
+    profile: Arc<Profile>,
+    base: String,
     id: String,
     name: String,
     home: Html,
+    data: LinkedData,
 }
 
 impl IMDB {
-    pub fn new(id: &str, name: &str) -> Result<Self, Error> {
-        let home = Html::parse_document(
-            &reqwest::get(&home_url!(id))
-                .map_err(|_| IMDBError::Home { id: id.to_owned() })?
-                .text()?
-        );
-
-        Ok(IMDB { id: id.to_owned(), name: name.to_owned(), home })
+    /// Fetches the home page of movie [id] using the IMDB transport, site root, retry budget
+    /// and selector profile in [settings]
+    pub fn new(settings: &Settings, id: &str, name: &str) -> Result<Self, Error> {
+        let base = settings.imdb_base.trim_end_matches('/');
+        let home = settings.imdb_retry.fetch(&*settings.fetcher, &home_url!(base, id))
+            .ok()
+            .filter(|response| response.is_success())
+            .ok_or(IMDBError::Home { id: id.to_owned() })?;
+        let home = Html::parse_document(&home.text());
+
+        Ok(IMDB {
+            fetcher: settings.fetcher.clone(),
+            retry: settings.imdb_retry,
+            profile: settings.profile.clone(),
+            base: base.to_owned(),
+            id: id.to_owned(),
+            name: name.to_owned(),
+            data: LinkedData::parse(&home, &settings.profile.linked_data),
+            home,
+        })
     }
 
     /// Returns the URL of the poster of movie with IMDB ID [id]
     pub fn get_poster(&self) -> Result<String, Error> {
-
-        let link = self.home.select(&*POSTER)
-            .map(|element| element.value().attr("href").unwrap())
-            .next()
-            .ok_or(IMDBError::Poster { name: self.name.clone() })?
-            .to_owned();
-        
-        let poster = reqwest::get(&abs_url!(link))?.text()?;
-
-        Ok(
-            Html::parse_document(&poster)
-                .select(&*IMAGE)
-                .next()
-                .map(|element| element.value().attr("content").unwrap())
-                .map(|link| RESIZE.replace_all(link, r"@._V1_.jpg").to_string())
-                .ok_or(IMDBError::Image { name: self.name.clone() })?
-                .to_owned()
-        )
+        self.find_poster().map(|(poster, _)| poster)
     }
 
     pub fn get_rating(&self) -> Result<String, Error> {
-        Ok(
-            self.home.select(&*RATING) 
-                .map(|element| element.value().attr("content").unwrap())
-                .next()
-                .ok_or(IMDBError::Rating { name: self.name.clone() })?
-                .to_owned()
-        )
+        self.find_rating().map(|(rating, _)| rating)
     }
 
     pub fn get_imdb_score(&self) -> Result<(f32, i32), Error> {
-        let value = self.home.select(&*SCORE_VALUE) 
-            .map(|element| f32::from_str(&element.inner_html()))
-            .next()
-            .ok_or(IMDBError::IMDBScore { name: self.name.clone() })??;
-        
-        let count = self.home.select(&*SCORE_COUNT) 
-            .map(|element| i32::from_str(&element.inner_html().replace(",", "")))
-            .next()
-            .ok_or(IMDBError::IMDBScore { name: self.name.clone() })??;
-
-        Ok((value, count))
+        self.find_imdb_score().map(|(score, _)| score)
     }
 
     pub fn get_metacritic_score(&self) -> Result<(f32, i32), Error> {
+        self.find_metacritic_score().map(|(score, _)| score)
+    }
+
+    pub fn get_summary(&self) -> Result<String, Error> {
+        self.find_summary().map(|(summary, _)| summary)
+    }
+
+    pub fn get_synopsis(&self) -> Result<String, Error> {
+        self.find_synopsis().map(|(synopsis, _)| synopsis)
+    }
+
+    /// Runs [extractor], returning where it found its value: `json-ld`, or the
+    /// selectors that matched.
+    pub fn probe(&self, extractor: Extractor) -> Result<String, Error> {
+        match extractor {
+        | Extractor::Poster => self.find_poster().map(|(_, source)| source),
+        | Extractor::Rating => self.find_rating().map(|(_, source)| source),
+        | Extractor::IMDBScore => self.find_imdb_score().map(|(_, source)| source),
+        | Extractor::MetacriticScore => self.find_metacritic_score().map(|(_, source)| source),
+        | Extractor::Summary => self.find_summary().map(|(_, source)| source),
+        | Extractor::Synopsis => self.find_synopsis().map(|(_, source)| source),
+        }
+    }
+
+    fn find_poster(&self) -> Result<(String, String), Error> {
+        if let Some(poster) = self.data.poster(&self.profile.resize) { return Ok((poster, LINKED_DATA.to_owned())) }
+
+        let (link, page_source) = self.profile.poster
+            .find(&self.home, |element| element.value().attr("href").map(str::to_owned))
+            .ok_or(IMDBError::Poster { name: self.name.clone() })?;
+
+        let poster = self.retry.fetch(&*self.fetcher, &abs_url!(self.base, link))?.text();
+
+        let (link, image_source) = self.profile.image
+            .find(&Html::parse_document(&poster), |element| element.value().attr("content").map(str::to_owned))
+            .ok_or(IMDBError::Image { name: self.name.clone() })?;
+
+        Ok((
+            self.profile.resize.replace_all(&link, r"@._V1_.jpg").to_string(),
+            format!("{} -> {}", page_source, image_source),
+        ))
+    }
+
+    fn find_rating(&self) -> Result<(String, String), Error> {
+        if let Some(rating) = self.data.rating() { return Ok((rating, LINKED_DATA.to_owned())) }
+        let (rating, source) = self.profile.rating
+            .find(&self.home, |element| element.value().attr("content").map(str::to_owned))
+            .ok_or(IMDBError::Rating { name: self.name.clone() })?;
+        Ok((rating, source.to_owned()))
+    }
+
+    fn find_imdb_score(&self) -> Result<((f32, i32), String), Error> {
+        if let Some(score) = self.data.score() { return Ok((score, LINKED_DATA.to_owned())) }
+        Ok(self.score(&self.home).ok_or(IMDBError::IMDBScore { name: self.name.clone() })?)
+    }
+
+    fn find_metacritic_score(&self) -> Result<((f32, i32), String), Error> {
 
         let metacritic = Html::parse_document(
-            &reqwest::get(&metacritic_url!(self.id))?.text()?
+            &self.retry.fetch(&*self.fetcher, &metacritic_url!(self.base, self.id))?.text()
         );
 
-        let value = metacritic.select(&*SCORE_VALUE)
-            .map(|element| f32::from_str(&element.inner_html()))
-            .next()
-            .ok_or(IMDBError::MetacriticScore { name: self.name.clone() })??;
+        Ok(self.score(&metacritic).ok_or(IMDBError::MetacriticScore { name: self.name.clone() })?)
+    }
+
+    /// Returns the score value and count on [page], if both are present.
+    fn score(&self, page: &Html) -> Option<((f32, i32), String)> {
+        let (value, value_source) = self.profile.score_value
+            .find(page, |element| f32::from_str(element.inner_html().trim()).ok())?;
 
-        let count = metacritic.select(&*SCORE_COUNT)
-            .map(|element| i32::from_str(&element.inner_html().replace(",", "")))
-            .next()
-            .ok_or(IMDBError::MetacriticScore { name: self.name.clone() })??;
+        let (count, count_source) = self.profile.score_count
+            .find(page, |element| i32::from_str(&element.inner_html().trim().replace(",", "")).ok())?;
 
-        Ok((value, count))
+        Some(((value, count), format!("{} + {}", value_source, count_source)))
     }
 
-    pub fn get_summary(&self) -> Result<String, Error> {
-        Ok(
-            self.home.select(&*SUMMARY)
-                .map(|element| {
-                    let s = HYPERLINK.replace_all(element.inner_html().trim(), |caps: &Captures| {
-                        if let None = caps.get(1) { caps[2].to_owned() } else { "".to_owned() }
-                    }).to_string();
-                    WRITTEN_BY.replace_all(&s, "").to_string()
-                })
-                .filter(|summary| !summary.is_empty())
-                .next()
-                .ok_or(IMDBError::Summary { name: self.name.clone() })?
-        )
+    fn find_summary(&self) -> Result<(String, String), Error> {
+        if let Some(summary) = self.data.summary() { return Ok((summary, LINKED_DATA.to_owned())) }
+        let (summary, source) = self.profile.summary
+            .find(&self.home, |element| {
+                let s = self.profile.hyperlink.replace_all(element.inner_html().trim(), |caps: &Captures| {
+                    if caps.get(1).is_none() { caps[2].to_owned() } else { "".to_owned() }
+                }).to_string();
+                Some(self.profile.written_by.replace_all(&s, "").to_string())
+                    .filter(|summary| !summary.is_empty())
+            })
+            .ok_or(IMDBError::Summary { name: self.name.clone() })?;
+        Ok((summary, source.to_owned()))
     }
 
-    pub fn get_synopsis(&self) -> Result<String, Error> {
-        let link = self.home.select(&*SYNOPSIS)
-            .map(|element| element.value().attr("href").unwrap())
-            .next()
-            .ok_or(IMDBError::Synopsis { name: self.name.clone() })?
-            .to_owned();
-
-        let synopsis = reqwest::get(&abs_url!(link))?.text()?;
-
-        Ok(
-            Html::parse_document(&synopsis)
-                .select(&*TEXT)
-                .filter(|element| element.value().id() != Some("no-synopsis-content"))
-                .map(|element| {
+    fn find_synopsis(&self) -> Result<(String, String), Error> {
+        let (link, link_source) = self.profile.synopsis
+            .find(&self.home, |element| element.value().attr("href").map(str::to_owned))
+            .ok_or(IMDBError::Synopsis { name: self.name.clone() })?;
+
+        let synopsis = self.retry.fetch(&*self.fetcher, &abs_url!(self.base, link))?.text();
+
+        let (synopsis, text_source) = self.profile.text
+            .find(&Html::parse_document(&synopsis), |element| {
+                if element.value().id() == Some("no-synopsis-content") { return None }
+                Some(
                     element.text()
                         .map(|s| s.trim().to_owned())
                         .collect::<Vec<_>>()
                         .join("\n")
-                })
-                .filter(|synopsis| !synopsis.is_empty())
-                .next()
-                .ok_or(IMDBError::Synopsis { name: self.name.clone() })?
-        )
+                ).filter(|synopsis| !synopsis.is_empty())
+            })
+            .ok_or(IMDBError::Synopsis { name: self.name.clone() })?;
+
+        Ok((synopsis, format!("{} -> {}", link_source, text_source)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linked_data_of_title_only() {
+        let home = Html::parse_document(r#"<html><head>
+            <script type="application/ld+json">{"@type": "BreadcrumbList", "description": "Home > Title"}</script>
+            <script type="application/ld+json">{"@type": "Organization", "image": "http://imdb.test/logo.png"}</script>
+            <script type="application/ld+json">{"@type": ["Movie"], "description": "Two imprisoned men bond.", "contentRating": "R"}</script>
+        </head></html>"#);
+
+        let data = LinkedData::parse(&home, &Profile::default().linked_data);
+        assert_eq!(data.summary(), Some("Two imprisoned men bond.".to_owned()));
+        assert_eq!(data.rating(), Some("R".to_owned()));
+        assert!(data.image.is_none());
+
+        let home = Html::parse_document(r#"<script type="application/ld+json">{"description": "Untyped"}</script>"#);
+        assert!(LinkedData::parse(&home, &Profile::default().linked_data).description.is_none());
     }
 }
@@ -10,8 +10,21 @@ extern crate reqwest;
 extern crate regex;
 extern crate stopwords;
 extern crate natural;
+extern crate rand;
+extern crate flate2;
 
+pub mod edges;
+pub mod export;
+pub mod fetch;
+pub mod health;
+pub mod limit;
+pub mod retry;
 pub mod tmdb;
 pub mod movie;
 pub mod imdb;
+pub mod people;
+pub mod policy;
+pub mod profile;
+pub mod resolve;
+pub mod tv;
 mod porter;
@@ -0,0 +1,102 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Token bucket rate limiter, safe to share between threads.
+pub struct Limiter {
+    rate: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl Limiter {
+    /// Allows [rate] requests per second on average, and at most [burst] back to back.
+    /// Panics unless [rate] is positive.
+    pub fn new(rate: f64, burst: u32) -> Self {
+        assert!(rate > 0.0, "request rate must be positive, got {}", rate);
+        let burst = f64::from(burst.max(1));
+        Limiter {
+            rate,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                refilled: Instant::now(),
+                paused_until: None,
+            }),
+        }
+    }
+
+    /// Blocks until the caller may send one request.
+    pub fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                let now = Instant::now();
+
+                if now > bucket.refilled {
+                    let elapsed = (now - bucket.refilled).as_secs_f64();
+                    bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+                    bucket.refilled = now;
+                }
+
+                match bucket.paused_until {
+                | Some(until) if until > now => until - now,
+                | _ if bucket.tokens >= 1.0 => {
+                    bucket.tokens -= 1.0;
+                    return
+                }
+                | _ => Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate),
+                }
+            };
+            thread::sleep(wait);
+        }
+    }
+
+    /// Holds back every caller for [delay], e.g. after the server asks us to slow down.
+    pub fn pause(&self, delay: Duration) {
+        let mut bucket = self.bucket.lock().unwrap();
+        let until = Instant::now() + delay;
+        if bucket.paused_until.iter().all(|&paused| paused < until) {
+            bucket.paused_until = Some(until);
+            bucket.tokens = 0.0;
+            bucket.refilled = until;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn burst_then_rate() {
+        let limiter = Limiter::new(20.0, 3);
+        let start = Instant::now();
+        for _ in 0..3 { limiter.acquire(); }
+        assert!(start.elapsed() < Duration::from_millis(40));
+
+        limiter.acquire();
+        assert!(start.elapsed() >= Duration::from_millis(45));
+    }
+
+    #[test]
+    fn pause_holds_back_callers() {
+        let limiter = Limiter::new(1000.0, 10);
+        let start = Instant::now();
+        limiter.pause(Duration::from_millis(100));
+        limiter.acquire();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "request rate must be positive")]
+    fn zero_rate_is_rejected() {
+        Limiter::new(0.0, 1);
+    }
+}
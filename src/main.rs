@@ -1,42 +1,599 @@
 #[macro_use] extern crate log;
+#[macro_use] extern crate failure;
+extern crate chrono;
 extern crate simplelog;
 extern crate movies;
-extern crate failure;
 extern crate reqwest;
 extern crate serde_json;
 
-use std::io::Write;
+use std::env;
+use std::process;
+use std::path::Path;
+use std::sync::Arc;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{ErrorKind, Write};
+use std::fs;
 use std::fs::File;
 use std::fs::create_dir;
+use chrono::{Duration, NaiveDate, Utc};
+use failure::Error;
 use simplelog::*;
 
 use movies::tmdb::*;
-use movies::movie::Movie;
+use movies::export::{self, ExportFilter};
+use movies::resolve::Query;
+use movies::people;
+use movies::edges;
+use movies::movie::{Index, Movie, Outcome, Settings};
+use movies::tv::TvShow;
+use movies::profile::{Profile, ProfileSource};
+use movies::fetch::Directory;
+use movies::health;
+use movies::retry::Backoff;
+use movies::retry::{classify, Class};
+
+/// Index of every crawled movie
+const MOVIES: &str = "movies.json";
+
+/// Index of every crawled TV show
+const SHOWS: &str = "shows.json";
+
+/// Saves one movie or show, returning its index entry
+type Save = fn(i32, &TMDB, &Settings) -> Result<Outcome, Error>;
+
+/// Lists the lines of a resolve crawl that matched no TMDB movie
+const UNRESOLVED: &str = "unresolved.txt";
+
+/// Records the end date of the last incremental crawl, beside the saved movies
+const LAST_SYNC: &str = "movies/last_sync";
+
+/// Lists the TMDB IDs the last incremental crawl failed to save, retried by the next one
+const PENDING: &str = "movies/pending";
+
+/// Number of movies crawled from a list when no count is given
+const DEFAULT_COUNT: usize = 1000;
+
+/// Share of checked titles a required IMDB field must be found on, when no threshold is given
+const DEFAULT_THRESHOLD: f64 = 0.9;
+
+/// Number of similar and recommended movies kept per movie when no count is given
+const DEFAULT_EDGES: usize = 20;
+
+/// Longest window, in days, accepted by TMDB's changes endpoint
+const CHANGES_WINDOW: i64 = 14;
 
 pub fn main() {
 
-    let mut tmdb = TMDB::default();
     let _ = WriteLogger::init(LevelFilter::Info, Config::default(), File::create("movies.log").unwrap());
     let _ = create_dir("posters");
     let _ = create_dir("movies");
-    let mut index = File::create("movies.json").unwrap();
-    let _ = index.write(b"[\n");
-    let max = tmdb.get_latest().unwrap();
-
-    for id in 1..max + 1 {
-        match Movie::save(id, &mut tmdb) {
-        | Err(err) => error!("{}: {}", id, err),
-        | Ok(movie) => {
-            info!("[SUCCESS] {}: {}", id, movie.title);
 
-            if let Ok(json) = serde_json::to_string(&movie) {
-                if let Err(e) = index.write_all(format!("    {},\n", json).as_bytes()) {
-                    error!("Write error for {}: {}", id, e);
-                }
+    let (connection, settings, flags, args) = match parse_options(env::args().skip(1)) {
+    | Ok(parsed) => parsed,
+    | Err(err) => {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+    };
+
+    // Offline modes run without TMDB credentials
+    let result = match args.first().map(|mode| mode.as_str()) {
+    | Some("profile") => print_profile(),
+    | Some("check-scraper") => check_scraper(&settings, &flags, &args[1..]),
+    | _ => connection.connect().and_then(|tmdb| run(&tmdb, settings, &flags, &args)),
+    };
+
+    if let Err(err) = result {
+        error!("{}", err);
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+/// Runs the mode named by the first of [args] against [tmdb].
+fn run(tmdb: &TMDB, settings: Settings, flags: &Flags, args: &[String]) -> Result<(), Error> {
+    // Only the movie and show crawls save images, so only they fetch the image configuration
+    let crawling = move || with_images(tmdb, settings);
+
+    match args.first().map(|mode| mode.as_str()) {
+    | None | Some("all") => crawl_all(tmdb, &crawling()),
+    | Some("changes") => crawl_changes(tmdb, &crawling(), flags, &args[1..]),
+    | Some("export") => crawl_export(tmdb, &crawling(), flags, &args[1..]),
+    | Some("list") => crawl_list(tmdb, &crawling(), &args[1..]),
+    | Some("discover") => crawl_discover(tmdb, &crawling(), &args[1..]),
+    | Some("resolve") => crawl_resolved(tmdb, &crawling(), &args[1..]),
+    | Some("shows") => crawl_shows(tmdb, &crawling(), &args[1..]),
+    | Some("people") => build_people(tmdb, flags),
+    | Some("edges") => build_edges(tmdb, &args[1..]),
+    | Some(mode) => Err(format_err!(
+        "Unknown mode {}; expected all, changes, export, list, discover, resolve, shows, people, edges, profile or check-scraper",
+        mode,
+    )),
+    }
+}
+
+/// Adds TMDB's image configuration to [settings], and creates the image directories they save into.
+fn with_images(tmdb: &TMDB, mut settings: Settings) -> Settings {
+    settings.images = tmdb.get_configuration()
+        .map_err(|err| warn!("[IMAGES] no TMDB configuration ({}); using IMDB posters", err))
+        .ok();
+    if settings.backdrop_size.is_some() { let _ = create_dir("backdrops"); }
+    if settings.profile_size.is_some() { let _ = create_dir("profiles"); }
+    settings
+}
+
+/// TMDB options, applied only by modes that talk to TMDB
+#[derive(Default)]
+struct Connection {
+    credentials: Credentials,
+    config: Option<String>,
+    base: Option<String>,
+    language: Option<String>,
+    region: Option<String>,
+}
+
+impl Connection {
+    fn connect(self) -> Result<TMDB, Error> {
+        // Explicit credentials win over the config file, which wins over the environment
+        let mut tmdb = match self.config {
+        | _ if !self.credentials.is_empty() => TMDB::with_credentials(self.credentials)?,
+        | Some(path) => TMDB::from_config(path)?,
+        | None => TMDB::from_env()?,
+        };
+        if let Some(base) = self.base { tmdb = tmdb.with_base_url(&base); }
+        if let Some(language) = self.language { tmdb = tmdb.with_language(&language); }
+        if let Some(region) = self.region { tmdb = tmdb.with_region(&region); }
+        Ok(tmdb)
+    }
+}
+
+/// Mode-specific options, ignored by the other modes
+struct Flags {
+    /// `export` keeps video entries
+    video: bool,
+    /// `people` fetches each person's TMDB details
+    details: bool,
+    /// `changes` also crawls movies missing from the index
+    new: bool,
+    /// `check-scraper` fails below this share of titles
+    threshold: f64,
+}
+
+impl Default for Flags {
+    fn default() -> Self {
+        Flags {
+            video: false,
+            details: false,
+            new: false,
+            threshold: DEFAULT_THRESHOLD,
+        }
+    }
+}
+
+/// Extracts the `--option[=value]` options from [args], returning the rest.
+fn parse_options<I: Iterator<Item = String>>(args: I) -> Result<(Connection, Settings, Flags, Vec<String>), Error> {
+    let mut connection = Connection::default();
+    let mut settings = Settings::default();
+    let mut flags = Flags::default();
+    let mut rest = Vec::new();
+
+    for arg in args {
+        let (option, value) = match arg.find('=') {
+        | _ if !arg.starts_with("--") => {
+            rest.push(arg);
+            continue
+        }
+        | Some(i) => (arg[..i].to_owned(), arg[i + 1..].to_owned()),
+        | None => (arg.clone(), String::new()),
+        };
+
+        let list = || value.split(',').map(|item| item.trim().to_owned()).filter(|item| !item.is_empty());
+
+        match option.as_str() {
+        | "--jobs" if value == "all" => settings.credits.all_crew = true,
+        | "--jobs" => settings.credits.jobs = list().collect(),
+        | "--departments" => settings.credits.departments = list().collect(),
+        | "--cast" if value == "all" => settings.credits.cast_limit = None,
+        | "--cast" => settings.credits.cast_limit = Some(value.parse()?),
+        | "--poster-size" => settings.poster_size = value,
+        | "--backdrop-size" => settings.backdrop_size = Some(value),
+        | "--profile-size" => settings.profile_size = Some(value),
+        | "--api-key" => connection.credentials.keys.extend(list()),
+        | "--bearer-token" => connection.credentials.tokens.extend(list()),
+        | "--config" => connection.config = Some(value),
+        | "--tmdb-base" => connection.base = Some(value),
+        | "--imdb-base" => settings.imdb_base = value,
+        | "--language" => connection.language = Some(value),
+        | "--region" => connection.region = Some(value),
+        | "--localize" => settings.localize = true,
+        | "--trailers" => settings.trailers = Some(list().collect()),
+        | "--profile" => settings.profile = Arc::new(Profile::load(&value)?),
+        | "--include-adult" => settings.policy.adult = true,
+        | "--min-runtime" => settings.policy.min_runtime = value.parse()?,
+        | "--min-votes" => settings.policy.min_votes = value.parse()?,
+        | "--status" => settings.policy.statuses = list().collect(),
+        | "--from-date" => settings.policy.from_date = Some(parse_date(&value)?.to_string()),
+        | "--to-date" => settings.policy.to_date = Some(parse_date(&value)?.to_string()),
+        | "--video" => flags.video = true,
+        | "--details" => flags.details = true,
+        | "--new" => flags.new = true,
+        | "--threshold" => {
+            flags.threshold = value.parse()?;
+            if !(0.0..=1.0).contains(&flags.threshold) {
+                return Err(format_err!("Invalid threshold {}; expected a share between 0 and 1", value))
             }
         }
+        | _ => return Err(format_err!("Unknown option {}", option)),
+        }
+    }
+
+    Ok((connection, settings, flags, rest))
+}
+
+/// IDs a [crawl] did not save
+#[derive(Default)]
+struct Unsaved {
+    /// Skipped by the crawl policy, or no longer on TMDB
+    dropped: Vec<i32>,
+    /// Failed fatally or ran out of retries
+    failed: Vec<i32>,
+}
+
+/// Saves every movie or show in [ids] with [save], passing each successfully saved one to [saved],
+/// and returns the rest.
+fn crawl<I, F>(ids: I, tmdb: &TMDB, settings: &Settings, save: Save, mut saved: F) -> Unsaved
+    where I: IntoIterator<Item = i32>, F: FnMut(Index)
+{
+    let mut unsaved = Unsaved::default();
+    for id in ids {
+        match save(id, tmdb, settings) {
+        | Err(err) => {
+            match classify(&err) {
+            | Class::Fatal => error!("{}: {}", id, err),
+            | Class::Retryable => error!("{}: [RETRIES EXHAUSTED] {}", id, err),
+            }
+            match err.downcast_ref::<TMDBError>() {
+            | Some(&TMDBError::NotFound { .. }) => unsaved.dropped.push(id),
+            | _ => unsaved.failed.push(id),
+            }
+        }
+        | Ok(Outcome::Skipped(reason)) => {
+            info!("[SKIPPED] {}: skipped ({})", id, reason);
+            unsaved.dropped.push(id);
+        }
+        | Ok(Outcome::Saved(movie)) => {
+            info!("[SUCCESS] {}: {}", id, movie.title);
+            saved(movie);
+        }
+        }
+    }
+    unsaved
+}
+
+/// Crawls [ids] from scratch, streaming the index into movies.json.
+fn crawl_fresh<I: IntoIterator<Item = i32>>(ids: I, tmdb: &TMDB, settings: &Settings) -> Result<(), Error> {
+    let mut index = IndexWriter::create(MOVIES)?;
+    crawl(ids, tmdb, settings, Movie::save, |movie| index.push(&movie));
+    index.finish()
+}
+
+/// Crawls every TMDB ID up to the latest one.
+fn crawl_all(tmdb: &TMDB, settings: &Settings) -> Result<(), Error> {
+    let max = tmdb.get_latest()?;
+    crawl_fresh(1..max + 1, tmdb, settings)
+}
+
+/// Re-saves indexed movies changed between `START` and `END` (default: the last sync and today),
+/// merging them into the existing movies.json and deleting those now skipped or gone from TMDB.
+/// `--new` also crawls changed movies missing from the index. Movies that fail to save are
+/// retried by the next run.
+fn crawl_changes(tmdb: &TMDB, settings: &Settings, flags: &Flags, args: &[String]) -> Result<(), Error> {
+    let start = match args.first() {
+    | Some(date) => parse_date(date)?,
+    | None => parse_date(
+        fs::read_to_string(LAST_SYNC)
+            .map_err(|_| format_err!("No {} file; pass a start date as YYYY-MM-DD", LAST_SYNC))?
+            .trim()
+    )?,
+    };
+
+    let end = match args.get(1) {
+    | Some(date) => parse_date(date)?,
+    | None => Utc::now().naive_utc().date(),
+    };
+
+    let mut movies = load_index(MOVIES)?;
+    let indexed = movies.values()
+        .filter(|movie| movie.tmdb_id != 0)
+        .map(|movie| (movie.tmdb_id, movie.id.clone()))
+        .collect::<BTreeMap<_, _>>();
+    if indexed.len() < movies.len() {
+        warn!("[CHANGES] {} indexed movies have no TMDB ID and won't be refreshed", movies.len() - indexed.len());
+    }
+
+    let mut ids = BTreeSet::new();
+    let mut from = start;
+    while from <= end {
+        let to = (from + Duration::days(CHANGES_WINDOW - 1)).min(end);
+        ids.extend(
+            tmdb.get_changes(&from.to_string(), &to.to_string())?
+                .into_iter()
+                .filter(|id| flags.new || indexed.contains_key(id))
+        );
+        from = to + Duration::days(1);
+    }
+
+    let pending = match fs::read_to_string(PENDING) {
+    | Ok(pending) => pending.lines().filter_map(|line| line.trim().parse::<i32>().ok()).collect(),
+    | Err(ref err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+    | Err(err) => Err(err)?,
+    };
+    info!("[CHANGES] {} movies changed between {} and {}, {} pending", ids.len(), start, end, pending.len());
+    ids.extend(pending);
+
+    let unsaved = crawl(ids, tmdb, settings, Movie::save, |movie| {
+        if let Some(previous) = indexed.get(&movie.tmdb_id) {
+            movies.remove(previous);
+            // TMDB moved the movie to another IMDB ID
+            if *previous != movie.id { remove_saved(previous); }
+        }
+        movies.insert(movie.id.clone(), movie);
+    });
+
+    for id in &unsaved.dropped {
+        if let Some(previous) = indexed.get(id) {
+            info!("[CHANGES] dropping {}", previous);
+            movies.remove(previous);
+            remove_saved(previous);
+        }
+    }
+
+    let mut index = IndexWriter::create(MOVIES)?;
+    for movie in movies.values() { index.push(movie); }
+    index.finish()?;
+
+    fs::write(PENDING, unsaved.failed.iter().map(|id| format!("{}\n", id)).collect::<String>())?;
+    if !unsaved.failed.is_empty() {
+        eprintln!("{} movies failed to save; the next changes crawl retries those listed in {}", unsaved.failed.len(), PENDING);
+    }
+
+    fs::write(LAST_SYNC, end.to_string())?;
+    Ok(())
+}
+
+/// Deletes the files saved for the movie with IMDB ID [id], so rebuilds from `movies/` don't bring it back.
+fn remove_saved(id: &str) {
+    if let Err(err) = Movie::remove(id) {
+        warn!("[CHANGES] couldn't delete the files of {}: {}", id, err);
+    }
+}
+
+/// Reads the index at [path] keyed by IMDB ID, rebuilding it from `movies/` if it can't be parsed.
+fn load_index(path: &str) -> Result<BTreeMap<String, Index>, Error> {
+    let movies = match File::open(path) {
+    | Err(ref err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+    | Err(err) => Err(err)?,
+    | Ok(file) => match serde_json::from_reader::<_, Vec<Index>>(file) {
+        | Ok(movies) => movies,
+        | Err(err) => {
+            warn!("[CHANGES] unreadable {} ({}); rebuilding it from movies/", path, err);
+            Movie::saved()?
+                .map(|movie| Index { id: movie.id, title: movie.title, tmdb_id: movie.tmdb_id })
+                .collect()
+        }
+    },
+    };
+
+    Ok(movies.into_iter().map(|movie| (movie.id.clone(), movie)).collect())
+}
+
+/// Crawls the movies listed in TMDB daily export `PATH`, optionally keeping only those
+/// at least as popular as `MIN_POPULARITY`; `--video` keeps video entries, and adult entries are
+/// kept only if the crawl policy saves them.
+fn crawl_export(tmdb: &TMDB, settings: &Settings, flags: &Flags, args: &[String]) -> Result<(), Error> {
+    let path = args.first().ok_or_else(|| format_err!("Missing path to TMDB export file"))?;
+    let filter = ExportFilter {
+        adult: settings.policy.adult,
+        video: flags.video,
+        min_popularity: match args.get(1) {
+        | Some(popularity) => popularity.parse()?,
+        | None => 0.0,
+        },
+    };
+
+    let ids = export::load(path, &filter)?;
+    info!("[EXPORT] {} movies selected from {}", ids.len(), path);
+    crawl_fresh(ids, tmdb, settings)
+}
+
+/// Crawls the top `COUNT` (default 1000) movies of TMDB list `NAME`.
+fn crawl_list(tmdb: &TMDB, settings: &Settings, args: &[String]) -> Result<(), Error> {
+    let list = match args.first().map(|name| name.as_str()) {
+    | Some("popular") => List::Popular,
+    | Some("top_rated") => List::TopRated,
+    | Some("now_playing") => List::NowPlaying,
+    | Some("upcoming") => List::Upcoming,
+    | _ => return Err(format_err!("Expected list popular, top_rated, now_playing or upcoming")),
+    };
+
+    let count = parse_count(args.get(1))?;
+    crawl_fresh(tmdb.get_top(&list, count)?, tmdb, settings)
+}
+
+/// Crawls the `COUNT` (default 1000) most popular movies matching `KEY=VALUE` filters
+/// `from`, `to`, `genre`, `votes` and `language`.
+fn crawl_discover(tmdb: &TMDB, settings: &Settings, args: &[String]) -> Result<(), Error> {
+    let (filters, count): (Vec<&String>, Vec<&String>) = args.iter().partition(|arg| arg.contains('='));
+    let mut discover = Discover::default();
+
+    for filter in filters {
+        let mut pair = filter.splitn(2, '=');
+        let (key, value) = (pair.next().unwrap(), pair.next().unwrap());
+        match key {
+        | "from" => discover.from_year = Some(value.parse()?),
+        | "to" => discover.to_year = Some(value.parse()?),
+        | "genre" => discover.genre = Some(value.parse()?),
+        | "votes" => discover.min_votes = Some(value.parse()?),
+        | "language" => discover.original_language = Some(value.to_owned()),
+        | _ => return Err(format_err!("Unknown filter {}; expected from, to, genre, votes or language", key)),
         }
     }
 
-    let _ = index.write(b"\n]");
+    let count = parse_count(count.first().cloned())?;
+    crawl_fresh(tmdb.get_top(&List::Discover(discover), count)?, tmdb, settings)
+}
+
+/// Crawls the movies listed in `PATH`, one IMDB ID or title per line, and
+/// reports the lines that could not be resolved to a TMDB ID.
+fn crawl_resolved(tmdb: &TMDB, settings: &Settings, args: &[String]) -> Result<(), Error> {
+    let path = args.first().ok_or_else(|| format_err!("Missing path to list of IMDB IDs or titles"))?;
+    let mut ids = Vec::new();
+    let mut unresolved = Vec::new();
+
+    let list = fs::read_to_string(path)?;
+    for line in list.lines() {
+        let query = match Query::parse(line) {
+        | Some(query) => query,
+        | None => continue,
+        };
+
+        match query.resolve(tmdb) {
+        | Ok(Some(id)) => ids.push(id),
+        | Ok(None) => {
+            warn!("[UNRESOLVED] {}", line);
+            unresolved.push(line);
+        }
+        | Err(err) => {
+            warn!("[UNRESOLVED] {}: {}", line, err);
+            unresolved.push(line);
+        }
+        }
+    }
+
+    info!("[RESOLVE] {} resolved, {} unresolved", ids.len(), unresolved.len());
+    fs::write(UNRESOLVED, unresolved.iter().map(|line| format!("{}\n", line)).collect::<String>())?;
+    if !unresolved.is_empty() {
+        eprintln!("{} entries could not be resolved; see {}", unresolved.len(), UNRESOLVED);
+    }
+
+    crawl_fresh(ids, tmdb, settings)
+}
+
+/// Crawls the top `COUNT` (default 1000) TV shows of TMDB list `NAME`, with every season,
+/// streaming the index into shows.json.
+fn crawl_shows(tmdb: &TMDB, settings: &Settings, args: &[String]) -> Result<(), Error> {
+    let list = match args.first().map(|name| name.as_str()) {
+    | Some("popular") => ShowList::Popular,
+    | Some("top_rated") => ShowList::TopRated,
+    | Some("on_the_air") => ShowList::OnTheAir,
+    | Some("airing_today") => ShowList::AiringToday,
+    | _ => return Err(format_err!("Expected list popular, top_rated, on_the_air or airing_today")),
+    };
+
+    let count = parse_count(args.get(1))?;
+    fs::create_dir_all("shows")?;
+
+    let mut index = IndexWriter::create(SHOWS)?;
+    crawl(tmdb.get_top_shows(&list, count)?, tmdb, settings, TvShow::save, |show| index.push(&show));
+    index.finish()
+}
+
+/// Builds per-person filmographies from the crawled movies; `--details` also fetches
+/// each person's TMDB details.
+fn build_people(tmdb: &TMDB, flags: &Flags) -> Result<(), Error> {
+    let count = people::build(if flags.details { Some(tmdb) } else { None })?;
+    info!("[PEOPLE] wrote {} filmographies", count);
+    Ok(())
+}
+
+/// Builds similar and recommended movie edge lists with up to `COUNT` (default 20)
+/// edges per crawled movie.
+fn build_edges(tmdb: &TMDB, args: &[String]) -> Result<(), Error> {
+    let count = match args.first() {
+    | Some(count) => count.parse()?,
+    | None => DEFAULT_EDGES,
+    };
+    let total = edges::build(tmdb, count)?;
+    info!("[EDGES] wrote {} edges", total);
+    Ok(())
+}
+
+/// Prints the built-in IMDB selector profile, as a starting point for `--profile`.
+fn print_profile() -> Result<(), Error> {
+    println!("{}", serde_json::to_string_pretty(&ProfileSource::default())?);
+    Ok(())
+}
+
+/// Runs every IMDB extractor against the title pages saved in directory `PATH`, or live against
+/// `IMDB_ID`s (default: a small sample), and prints which fields were found and by what.
+/// Fails if a required field was found on fewer than `--threshold` (default 0.9) of the titles.
+fn check_scraper(settings: &Settings, flags: &Flags, args: &[String]) -> Result<(), Error> {
+    let report = match args.first() {
+    | Some(path) if Path::new(path.as_str()).is_dir() => {
+        let saved = Settings {
+            fetcher: Arc::new(Directory::new(path.as_str())),
+            imdb_base: settings.imdb_base.clone(),
+            profile: settings.profile.clone(),
+            imdb_retry: Backoff::none(),
+            ..Settings::default()
+        };
+        let ids = health::saved_ids(path)?;
+        if ids.is_empty() {
+            return Err(format_err!("No saved IMDB title pages in {}", path))
+        }
+        health::check(&saved, &ids)
+    }
+    | Some(_) => health::check(settings, &args.iter().map(|id| id.to_string()).collect::<Vec<_>>()),
+    | None => health::check(settings, &health::SAMPLE.iter().map(|id| id.to_string()).collect::<Vec<_>>()),
+    };
+
+    print!("{}", report);
+
+    let failing = report.failing(flags.threshold);
+    if failing.is_empty() {
+        Ok(())
+    } else {
+        Err(format_err!("Required IMDB fields found on under {:.0}% of titles: {}", flags.threshold * 100.0, failing.join(", ")))
+    }
+}
+
+fn parse_count(count: Option<&String>) -> Result<usize, Error> {
+    match count {
+    | Some(count) => Ok(count.parse()?),
+    | None => Ok(DEFAULT_COUNT),
+    }
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, Error> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format_err!("Invalid date {}; expected YYYY-MM-DD", date))
+}
+
+/// Writes movies.json or shows.json as a JSON array with one [Index] per line.
+struct IndexWriter {
+    file: File,
+    empty: bool,
+}
+
+impl IndexWriter {
+    fn create(path: &str) -> Result<Self, Error> {
+        let mut file = File::create(path)?;
+        file.write_all(b"[\n")?;
+        Ok(IndexWriter { file, empty: true })
+    }
+
+    fn push(&mut self, movie: &Index) {
+        if let Ok(json) = serde_json::to_string(movie) {
+            let separator = if self.empty { "" } else { ",\n" };
+            if let Err(e) = self.file.write_all(format!("{}    {}", separator, json).as_bytes()) {
+                error!("Write error for {}: {}", movie.id, e);
+            }
+            self.empty = false;
+        }
+    }
+
+    fn finish(mut self) -> Result<(), Error> {
+        self.file.write_all(b"\n]\n")?;
+        Ok(())
+    }
 }
@@ -3,14 +3,22 @@ use std::collections::HashSet;
 use stopwords::{NLTK, Language, Stopwords};
 use failure::Error;
 
-use std::io::Write;
+use std::collections::BTreeMap;
+use std::io::{ErrorKind, Write};
+use std::fs;
 use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+use serde::{Deserialize, Deserializer};
 use serde_json;
 
+use fetch::*;
 use tmdb::*;
 use imdb::*;
 use porter::*;
-use reqwest;
+use policy::Policy;
+use profile::Profile;
+use retry::Backoff;
 
 lazy_static! {
     static ref STOP_WORDS: HashSet<&'static str> = NLTK::stopwords(Language::English)
@@ -20,32 +28,124 @@ lazy_static! {
         .collect();
 }
 
+/// TMDB gender codes: 0 unknown, 1 female, 2 male, 3 non-binary
+pub type Gender = i32;
+
 #[derive(Deserialize, Serialize)]
 pub struct Cast {
+    /// TMDB person ID
+    #[serde(default)]
+    pub id: i32,
+    #[serde(default)]
+    pub credit_id: String,
     pub character: String,
     pub name: String,  
+    #[serde(default)]
+    pub gender: Gender,
+    #[serde(default)]
+    pub order: i32,
+    pub profile_path: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct Crew {
+    /// TMDB person ID
+    #[serde(default)]
+    pub id: i32,
+    #[serde(default)]
+    pub credit_id: String,
+    #[serde(default)]
+    pub department: String,
     pub job: String,
     pub name: String,
+    #[serde(default)]
+    pub gender: Gender,
+    pub profile_path: Option<String>,
+}
+
+/// Crew grouped by department
+pub type Departments = BTreeMap<String, Vec<Crew>>;
+
+/// Groups [crew] by department, keeping their order within each department.
+pub fn by_department<I: IntoIterator<Item = Crew>>(crew: I) -> Departments {
+    let mut departments = BTreeMap::new();
+    for crew in crew {
+        departments.entry(crew.department.clone()).or_insert_with(Vec::new).push(crew);
+    }
+    departments
 }
 
-#[derive(Serialize)]
+/// Reads crew grouped by department, or the flat list of jobs saved before crew was grouped.
+fn deserialize_crew<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Departments, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Saved {
+        Grouped(Departments),
+        Listed(Vec<Crew>),
+    }
+
+    Ok(match Saved::deserialize(deserializer)? {
+    | Saved::Grouped(departments) => departments,
+    | Saved::Listed(crew) => by_department(crew.into_iter().map(|mut crew| {
+        if crew.department.is_empty() {
+            crew.department = default_department(&crew.job).to_owned();
+        }
+        crew
+    })),
+    })
+}
+
+/// Returns the TMDB department of the jobs kept before departments were recorded
+fn default_department(job: &str) -> &'static str {
+    match job {
+    | "Director" => "Directing",
+    | "Producer" => "Production",
+    | "Writer" | "Screenplay" => "Writing",
+    | "Original Music Composer" => "Sound",
+    | _ => "",
+    }
+}
+
+#[derive(Deserialize, Serialize)]
 pub struct Movie {
     pub id: String,
+    #[serde(default)]
+    pub tmdb_id: i32,
     pub cast: Vec<Cast>,
-    pub crew: Vec<Crew>,
+    #[serde(deserialize_with = "deserialize_crew")]
+    pub crew: Departments,
     pub title: String,
+    pub original_title: Option<String>,
+    pub tagline: Option<String>,
     pub genres: Vec<String>,
     pub keywords: Vec<String>,
+    pub collection: Option<Collection>,
+    #[serde(default)]
+    pub production_companies: Vec<Company>,
+    #[serde(default)]
+    pub production_countries: Vec<Country>,
+    #[serde(default)]
+    pub spoken_languages: Vec<SpokenLanguage>,
     pub original_language: String,
+    pub homepage: Option<String>,
     pub rating: String,
+    pub status: Option<String>,
     pub release_date: String,
+    #[serde(default)]
+    pub releases: Releases,
+    #[serde(default)]
+    pub budget: f32,
     pub revenue: f32,
     pub runtime: i32, 
+    #[serde(default)]
+    pub popularity: f32,
     pub summary: String,
+    #[serde(default)]
+    pub translations: Localizations,
+    #[serde(default)]
+    pub alternative_titles: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub trailers: Vec<Video>,
     pub tokens: Vec<String>,
     pub tmdb_score_value: f32,
     pub tmdb_score_count: i32,
@@ -59,23 +159,157 @@ pub struct Movie {
 pub struct Index {
     pub id: String,
     pub title: String,
+    /// 0 in indexes written before TMDB IDs were recorded
+    #[serde(default)]
+    pub tmdb_id: i32,
+}
+
+/// Result of trying to save one movie or show
+pub enum Outcome {
+    Saved(Index),
+    /// Excluded by the crawl [Policy], for this reason
+    Skipped(String),
+}
+
+/// Per-crawl settings shared by every call to [Movie::save].
+pub struct Settings {
+    /// Transport for IMDB pages and poster downloads
+    pub fetcher: Arc<dyn Fetcher>,
+    /// Site root used for IMDB scraping, e.g. a local fixture server
+    pub imdb_base: String,
+    /// Selectors and patterns used for IMDB scraping
+    pub profile: Arc<Profile>,
+    /// Credits kept for each movie
+    pub credits: CreditFilter,
+    /// Movies and shows kept by the crawl
+    pub policy: Policy,
+    /// Retry budget for IMDB pages
+    pub imdb_retry: Backoff,
+    /// Retry budget for poster downloads
+    pub poster_retry: Backoff,
+    /// Also fetch translations and alternative titles
+    pub localize: bool,
+    /// Also fetch official trailers in these ISO 639-1 languages, or in the TMDB language if empty
+    pub trailers: Option<Vec<String>>,
+    /// TMDB image locations; posters come from IMDB alone if unset
+    pub images: Option<ImageConfig>,
+    /// TMDB poster size, e.g. `w185`, `w500` or `original`
+    pub poster_size: String,
+    /// Also save TMDB backdrops into `backdrops/`, at this size
+    pub backdrop_size: Option<String>,
+    /// Also save TMDB cast profile images into `profiles/`, at this size
+    pub profile_size: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            fetcher: Arc::new(Reqwest::default()),
+            imdb_base: IMDB_BASE.to_owned(),
+            profile: Arc::new(Profile::default()),
+            credits: CreditFilter::default(),
+            policy: Policy::default(),
+            imdb_retry: Backoff::default(),
+            poster_retry: Backoff::default(),
+            localize: false,
+            trailers: None,
+            images: None,
+            poster_size: "w500".to_owned(),
+            backdrop_size: None,
+            profile_size: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Downloads image [link] within the poster retry budget.
+    fn download(&self, link: &str) -> Result<Vec<u8>, Error> {
+        let response = self.poster_retry.fetch(&*self.fetcher, link)?;
+        if response.is_success() {
+            Ok(response.body)
+        } else {
+            Err(FetchError::Status { status: response.status })?
+        }
+    }
+
+    /// Downloads image [link] into [file], unless it was saved before.
+    fn save_image(&self, link: &str, file: &str) -> Result<(), Error> {
+        if !Path::new(file).exists() {
+            let image = self.download(link)?;
+            File::create(file)?.write_all(&image)?;
+        }
+        Ok(())
+    }
+
+    /// Saves `posters/{id}.jpg` from TMDB [poster_path] if possible, or else from [imdb].
+    pub fn save_poster(&self, imdb: Option<&IMDB>, poster_path: &Option<String>, title: &str, id: &str) -> Result<(), Error> {
+        let tmdb_poster = match (&self.images, poster_path) {
+        | (&Some(ref images), &Some(ref path)) => {
+            self.download(&images.url(&images.poster_sizes, &self.poster_size, path))
+                .map_err(|error| warn!("{}: TMDB poster failed ({}); trying IMDB", title, error))
+                .ok()
+        }
+        | _ => None,
+        };
+
+        let poster = match (tmdb_poster, imdb) {
+        | (Some(poster), _) => poster,
+        | (None, Some(imdb)) => self.download(&imdb.get_poster()?)?,
+        | (None, None) => Err(IMDBError::Poster { name: title.to_owned() })?,
+        };
+        File::create(format!("posters/{}.jpg", id))?.write_all(&poster)?;
+        Ok(())
+    }
 }
 
 impl Movie {
+
+    /// Iterates over every movie saved in `movies/`, skipping unreadable records.
+    pub fn saved() -> Result<impl Iterator<Item = Movie>, Error> {
+        Ok(
+            fs::read_dir("movies")?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().and_then(|extension| extension.to_str()) == Some("json"))
+                .filter_map(|path| {
+                    File::open(&path)
+                        .map_err(Error::from)
+                        .and_then(|file| serde_json::from_reader(file).map_err(Error::from))
+                        .map_err(|err| warn!("skipping {}: {}", path.display(), err))
+                        .ok()
+                })
+        )
+    }
+
+    /// Deletes the record, poster and backdrop saved for IMDB ID [id], ignoring those missing.
+    pub fn remove(id: &str) -> Result<(), Error> {
+        for file in &[format!("movies/{}.json", id), format!("posters/{}.jpg", id), format!("backdrops/{}.jpg", id)] {
+            match fs::remove_file(file) {
+            | Err(ref err) if err.kind() == ErrorKind::NotFound => (),
+            | result => result?,
+            }
+        }
+        Ok(())
+    }
     
-    pub fn save(id: i32, tmdb: &mut TMDB) -> Result<Index, Error> {
-        let movie = tmdb.get_raw_movie(id)?;
-        let (cast, crew) = tmdb.get_people(id)?;
-        let keywords = tmdb.get_keywords(id)?;
+    pub fn save(id: i32, tmdb: &TMDB, settings: &Settings) -> Result<Outcome, Error> {
+        let MovieBundle { movie, cast, crew, keywords, releases, translations, alternative_titles, trailers } =
+            tmdb.get_movie_bundle(id, &settings.credits, settings.localize, settings.trailers.as_deref())?;
+
+        if let Some(reason) = settings.policy.check_movie(&movie) {
+            return Ok(Outcome::Skipped(reason))
+        }
 
-        let imdb = IMDB::new(&movie.imdb_id, &movie.title)?;
+        let imdb = IMDB::new(settings, &movie.imdb_id, &movie.title)?;
 
-        let rating = imdb
+        let rating = match us_certification(&releases) {
+        | Some(rating) => rating,
+        | None => imdb
             .get_rating()
             .unwrap_or_else(|_| {
                 warn!("{} has no MPAA rating", movie.title.clone());
                 "NOT RATED".to_owned()
-            });
+            }),
+        };
 
         let (imdb_score_value, imdb_score_count) = imdb.get_imdb_score()?;
 
@@ -86,59 +320,59 @@ impl Movie {
                 (0., 0)
             });
 
-        let summary = imdb
-            .get_summary()
-            .or_else(|error| {
-                match &movie.overview {
-                | &None => Err(IMDBError::Summary { name: movie.title.clone() }),
-                | &Some(ref text) => {
-                    if text.is_empty() {
-                        Err(IMDBError::Summary { name: movie.title.clone() })
-                    } else {
-                        warn!("{}; substituting overview of length {}", error, text.len());
-                        Ok(text.to_owned())
-                    }
-                }
-                }
-            })?;
-
-        let synopsis = imdb
-            .get_synopsis()
-            .and_then(|text| {
-                info!("[TOKENS] {}: synopsis of length {}", id, text.len());
-                Ok(text)
-            }).unwrap_or_else(|_| {
-                warn!("[TOKENS] {}: summary of length {}", id, summary.len());
-                summary.clone()
-            });
+        let (summary, tokens) = describe(&imdb, id, &movie.title, &movie.overview)?;
+
+        settings.save_poster(Some(&imdb), &movie.poster_path, &movie.title, &movie.imdb_id)?;
 
-        let tokens = tokenize(&synopsis)
-            .into_iter()
-            .filter(|word| !STOP_WORDS.contains(word))
-            .map(|word| Porter::stem(word))
-            .collect::<Vec<_>>();
+        if let (Some(images), Some(size), Some(path)) = (settings.images.as_ref(), settings.backdrop_size.as_ref(), movie.backdrop_path.as_ref()) {
+            let link = images.url(&images.backdrop_sizes, size, path);
+            if let Err(error) = settings.save_image(&link, &format!("backdrops/{}.jpg", movie.imdb_id)) {
+                warn!("{}: backdrop failed ({})", movie.title, error);
+            }
+        }
 
-        let link = imdb.get_poster()?;
-        let mut poster_file = File::create(format!("posters/{}.jpg", movie.imdb_id))?;
-        let mut poster = reqwest::get(&link)?;
-        poster.copy_to(&mut poster_file)?;
+        if let (Some(images), Some(size)) = (settings.images.as_ref(), settings.profile_size.as_ref()) {
+            for person in &cast {
+                if let Some(ref path) = person.profile_path {
+                    let link = images.url(&images.profile_sizes, size, path);
+                    if let Err(error) = settings.save_image(&link, &format!("profiles/{}.jpg", person.id)) {
+                        warn!("{}: profile failed ({})", person.name, error);
+                    }
+                }
+            }
+        }
 
         let mut movie_file = File::create(format!("movies/{}.json", movie.imdb_id))?;
         movie_file.write_all(
             serde_json::to_string(
                 &Movie {
                     id: movie.imdb_id.clone(),
+                    tmdb_id: movie.id,
                     cast,
                     crew,
                     title: movie.title.clone(),
+                    original_title: non_empty(movie.original_title),
+                    tagline: non_empty(movie.tagline),
                     genres: movie.genres.into_iter().map(|genre| genre.name).collect(),
                     keywords,
+                    collection: movie.belongs_to_collection,
+                    production_companies: movie.production_companies,
+                    production_countries: movie.production_countries,
+                    spoken_languages: movie.spoken_languages,
                     original_language: movie.original_language,
+                    homepage: non_empty(movie.homepage),
                     rating,
+                    status: non_empty(movie.status),
                     release_date: movie.release_date,
+                    releases,
+                    budget: movie.budget,
                     revenue: movie.revenue,
                     runtime: movie.runtime, 
+                    popularity: movie.popularity,
                     summary,
+                    translations,
+                    alternative_titles,
+                    trailers,
                     tokens,
                     tmdb_score_value: movie.vote_average,
                     tmdb_score_count: movie.vote_count,
@@ -150,9 +384,98 @@ impl Movie {
             )?.as_bytes()
         )?;
 
-        Ok(Index{
+        Ok(Outcome::Saved(Index{
             id: movie.imdb_id,
             title: movie.title,
-        })
+            tmdb_id: movie.id,
+        }))
+    }
+}
+
+/// Returns the IMDB summary of [title], falling back to TMDB's [overview], and the
+/// tokens of the IMDB synopsis, falling back to the summary. [id] is the TMDB ID.
+pub fn describe(imdb: &IMDB, id: i32, title: &str, overview: &Option<String>) -> Result<(String, Vec<String>), Error> {
+    let summary = imdb
+        .get_summary()
+        .or_else(|error| {
+            match *overview {
+            | Some(ref text) if !text.is_empty() => {
+                warn!("{}; substituting overview of length {}", error, text.len());
+                Ok(text.to_owned())
+            }
+            | _ => Err(IMDBError::Summary { name: title.to_owned() }),
+            }
+        })?;
+
+    let synopsis = match imdb.get_synopsis() {
+    | Ok(text) => {
+        info!("[TOKENS] {}: synopsis of length {}", id, text.len());
+        text
+    }
+    | Err(_) => {
+        warn!("[TOKENS] {}: summary of length {}", id, summary.len());
+        summary.clone()
+    }
+    };
+
+    let tokens = tokens(&synopsis);
+    Ok((summary, tokens))
+}
+
+/// Splits [text] into stemmed words, dropping English stop words. Both the stemmer
+/// and the stop words assume English text.
+pub fn tokens(text: &str) -> Vec<String> {
+    tokenize(text)
+        .into_iter()
+        .filter(|word| !STOP_WORDS.contains(word))
+        .map(Porter::stem)
+        .collect()
+}
+
+/// TMDB sends empty strings for many missing values
+pub fn non_empty(text: Option<String>) -> Option<String> {
+    text.filter(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_flat_crew() {
+        // Record saved before crew was grouped by department
+        let movie: Movie = serde_json::from_str(r#"{
+            "id": "tt0111161",
+            "cast": [{"character": "Andy Dufresne", "name": "Tim Robbins"}],
+            "crew": [
+                {"job": "Director", "name": "Frank Darabont"},
+                {"job": "Screenplay", "name": "Frank Darabont"},
+                {"job": "Producer", "name": "Niki Marvin"}
+            ],
+            "title": "The Shawshank Redemption",
+            "genres": ["Drama"],
+            "keywords": ["prison"],
+            "original_language": "en",
+            "rating": "R",
+            "release_date": "1994-09-23",
+            "revenue": 28341469.0,
+            "runtime": 142,
+            "summary": "Two imprisoned men bond over a number of years.",
+            "tokens": ["imprison"],
+            "tmdb_score_value": 8.7,
+            "tmdb_score_count": 24000,
+            "imdb_score_value": 9.3,
+            "imdb_score_count": 2500000,
+            "meta_score_value": 80.0,
+            "meta_score_count": 20
+        }"#).unwrap();
+
+        assert_eq!(movie.tmdb_id, 0);
+        assert_eq!(movie.crew.keys().collect::<Vec<_>>(), vec!["Directing", "Production", "Writing"]);
+        assert_eq!(movie.crew["Writing"][0].job, "Screenplay");
+
+        let saved = serde_json::to_string(&movie).unwrap();
+        let movie: Movie = serde_json::from_str(&saved).unwrap();
+        assert_eq!(movie.crew["Directing"][0].name, "Frank Darabont");
     }
 }
@@ -0,0 +1,126 @@
+use failure::Error;
+use serde_json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+
+use movie::Movie;
+use tmdb::{Person, TMDB};
+
+/// One credit in a person's filmography
+#[derive(Deserialize, Serialize)]
+pub struct Role {
+    /// IMDB ID of the movie
+    pub movie: String,
+    pub title: String,
+    pub release_date: String,
+    /// `Acting` for cast credits, otherwise the crew department
+    pub department: String,
+    /// Character played, for cast credits
+    pub character: Option<String>,
+    /// Job held, for crew credits
+    pub job: Option<String>,
+}
+
+/// Everything the crawl knows about one person, saved as `people/{id}.json`
+#[derive(Deserialize, Serialize)]
+pub struct Filmography {
+    /// TMDB person ID
+    pub id: i32,
+    pub name: String,
+    /// Details from [TMDB::get_person], if they were ever fetched
+    pub details: Option<Person>,
+    /// Characters played, in order of first appearance
+    pub characters: Vec<String>,
+    /// IMDB IDs of every movie credited, by release date
+    pub movies: Vec<String>,
+    /// Every credit, by release date
+    pub roles: Vec<Role>,
+}
+
+/// Entry of `people.json`
+#[derive(Serialize)]
+pub struct PersonIndex {
+    pub id: i32,
+    pub name: String,
+    pub movies: usize,
+}
+
+/// Builds `people/{id}.json` for every person credited in `movies/*.json`, plus the
+/// `people.json` index, and returns the number of people written.
+///
+/// Person details are fetched from [tmdb] if given, and otherwise carried over from
+/// any previously built filmography.
+pub fn build(tmdb: Option<&TMDB>) -> Result<usize, Error> {
+    let mut people: BTreeMap<i32, Filmography> = BTreeMap::new();
+
+    for movie in Movie::saved()? {
+        let role = |department: &str, character: Option<&String>, job: Option<&String>| Role {
+            movie: movie.id.clone(),
+            title: movie.title.clone(),
+            release_date: movie.release_date.clone(),
+            department: department.to_owned(),
+            character: character.cloned(),
+            job: job.cloned(),
+        };
+
+        for cast in movie.cast.iter().filter(|cast| cast.id != 0) {
+            filmography(&mut people, cast.id, &cast.name)
+                .roles
+                .push(role("Acting", Some(&cast.character), None));
+        }
+
+        for crew in movie.crew.values().flatten().filter(|crew| crew.id != 0) {
+            filmography(&mut people, crew.id, &crew.name)
+                .roles
+                .push(role(&crew.department, None, Some(&crew.job)));
+        }
+    }
+
+    fs::create_dir_all("people")?;
+    let mut index = Vec::new();
+
+    for (id, mut person) in people {
+        person.roles.sort_by(|a, b| a.release_date.cmp(&b.release_date).then(a.movie.cmp(&b.movie)));
+
+        for role in &person.roles {
+            if !person.movies.contains(&role.movie) {
+                person.movies.push(role.movie.clone());
+            }
+            if let Some(ref character) = role.character {
+                if !person.characters.contains(character) {
+                    person.characters.push(character.clone());
+                }
+            }
+        }
+
+        let path = format!("people/{}.json", id);
+        person.details = match tmdb {
+        | Some(tmdb) => tmdb.get_person(id)
+            .map_err(|err| warn!("[PEOPLE] no details for {}: {}", id, err))
+            .ok(),
+        | None => File::open(&path)
+            .ok()
+            .and_then(|file| serde_json::from_reader::<_, Filmography>(file).ok())
+            .and_then(|previous| previous.details),
+        };
+
+        File::create(&path)?.write_all(serde_json::to_string(&person)?.as_bytes())?;
+        index.push(PersonIndex { id, name: person.name, movies: person.movies.len() });
+    }
+
+    File::create("people.json")?.write_all(serde_json::to_string(&index)?.as_bytes())?;
+    Ok(index.len())
+}
+
+fn filmography<'a>(people: &'a mut BTreeMap<i32, Filmography>, id: i32, name: &str) -> &'a mut Filmography {
+    people.entry(id).or_insert_with(|| Filmography {
+        id,
+        name: name.to_owned(),
+        details: None,
+        characters: Vec::new(),
+        movies: Vec::new(),
+        roles: Vec::new(),
+    })
+}
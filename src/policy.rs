@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+
+use tmdb::{RawMovie, RawShow};
+
+/// Decides which movies and shows a crawl saves; the rest are skipped, not failed.
+pub struct Policy {
+    /// Keep titles TMDB marks as adult
+    pub adult: bool,
+    /// Shortest runtime kept, in minutes; applies to movies only
+    pub min_runtime: i32,
+    /// Fewest TMDB votes kept
+    pub min_votes: i32,
+    /// TMDB statuses kept, e.g. `Released`; empty keeps every status
+    pub statuses: HashSet<String>,
+    /// Earliest release or first air date kept, formatted `YYYY-MM-DD`
+    pub from_date: Option<String>,
+    /// Latest release or first air date kept, formatted `YYYY-MM-DD`
+    pub to_date: Option<String>,
+}
+
+impl Default for Policy {
+    /// Skips adult titles and movies without a runtime
+    fn default() -> Self {
+        Policy {
+            adult: false,
+            min_runtime: 1,
+            min_votes: 0,
+            statuses: HashSet::new(),
+            from_date: None,
+            to_date: None,
+        }
+    }
+}
+
+impl Policy {
+    /// Returns why [movie] should be skipped, or `None` to keep it.
+    pub fn check_movie(&self, movie: &RawMovie) -> Option<String> {
+        if movie.runtime < self.min_runtime {
+            return Some(format!("runtime {} under {} minutes", movie.runtime, self.min_runtime))
+        }
+        self.check(movie.adult, movie.vote_count, &movie.status, &movie.release_date)
+    }
+
+    /// Returns why [show] should be skipped, or `None` to keep it.
+    pub fn check_show(&self, show: &RawShow) -> Option<String> {
+        let date = show.first_air_date.clone().unwrap_or_default();
+        self.check(show.adult, show.vote_count, &show.status, &date)
+    }
+
+    fn check(&self, adult: bool, votes: i32, status: &Option<String>, date: &str) -> Option<String> {
+        let status = status.as_ref().map_or("", |status| status.as_str());
+
+        if adult && !self.adult {
+            return Some("adult".to_owned())
+        }
+        if votes < self.min_votes {
+            return Some(format!("{} votes under {}", votes, self.min_votes))
+        }
+        if !self.statuses.is_empty() && !self.statuses.contains(status) {
+            return Some(format!("status {}", if status.is_empty() { "unknown" } else { status }))
+        }
+        if date.is_empty() && (self.from_date.is_some() || self.to_date.is_some()) {
+            return Some("no release date".to_owned())
+        }
+        if let Some(from) = self.from_date.as_ref().filter(|from| date < from.as_str()) {
+            return Some(format!("released {} before {}", date, from))
+        }
+        self.to_date.as_ref()
+            .filter(|to| date > to.as_str())
+            .map(|to| format!("released {} after {}", date, to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    fn movie(runtime: i32, status: &str, release_date: &str) -> RawMovie {
+        serde_json::from_str(&format!(
+            r#"{{"id": 1, "imdb_id": "tt0000001", "title": "Movie", "adult": false,
+                "budget": 0, "genres": [], "original_language": "en", "popularity": 0,
+                "release_date": "{}", "revenue": 0, "runtime": {}, "status": "{}",
+                "vote_average": 0, "vote_count": 10}}"#,
+            release_date, runtime, status,
+        )).unwrap()
+    }
+
+    fn show(first_air_date: Option<&str>) -> RawShow {
+        serde_json::from_str(&format!(
+            r#"{{"id": 1, "name": "Show", "first_air_date": {}, "genres": [],
+                "number_of_episodes": 1, "number_of_seasons": 1, "original_language": "en",
+                "popularity": 0, "status": "Ended", "vote_average": 0, "vote_count": 10}}"#,
+            first_air_date.map_or("null".to_owned(), |date| format!("\"{}\"", date)),
+        )).unwrap()
+    }
+
+    fn dated(from: &str, to: &str) -> Policy {
+        Policy { from_date: Some(from.to_owned()), to_date: Some(to.to_owned()), ..Policy::default() }
+    }
+
+    #[test]
+    fn runtime_and_votes() {
+        let policy = Policy::default();
+        assert!(policy.check_movie(&movie(0, "Released", "1994-09-23")).is_some());
+        assert_eq!(policy.check_movie(&movie(1, "Released", "1994-09-23")), None);
+
+        let policy = Policy { min_votes: 10, ..Policy::default() };
+        assert_eq!(policy.check_movie(&movie(90, "Released", "1994-09-23")), None);
+        let policy = Policy { min_votes: 11, ..Policy::default() };
+        assert!(policy.check_movie(&movie(90, "Released", "1994-09-23")).is_some());
+    }
+
+    #[test]
+    fn dates_are_inclusive() {
+        let policy = dated("1994-01-01", "1994-12-31");
+        assert_eq!(policy.check_movie(&movie(90, "Released", "1994-01-01")), None);
+        assert_eq!(policy.check_movie(&movie(90, "Released", "1994-12-31")), None);
+        assert!(policy.check_movie(&movie(90, "Released", "1993-12-31")).is_some());
+        assert!(policy.check_movie(&movie(90, "Released", "1995-01-01")).is_some());
+        assert_eq!(policy.check_movie(&movie(90, "Released", "")), Some("no release date".to_owned()));
+
+        assert_eq!(policy.check_show(&show(Some("1994-06-01"))), None);
+        assert_eq!(policy.check_show(&show(None)), Some("no release date".to_owned()));
+        assert_eq!(Policy::default().check_show(&show(None)), None);
+    }
+
+    #[test]
+    fn statuses() {
+        let mut policy = Policy::default();
+        policy.statuses.insert("Released".to_owned());
+        assert_eq!(policy.check_movie(&movie(90, "Released", "1994-09-23")), None);
+        assert_eq!(policy.check_movie(&movie(90, "Rumored", "1994-09-23")), Some("status Rumored".to_owned()));
+        assert_eq!(policy.check_show(&show(None)), Some("status Ended".to_owned()));
+    }
+}
@@ -0,0 +1,180 @@
+use failure::Error;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use serde_json;
+use std::fs::File;
+use std::path::Path;
+
+/// Newest profile format this build understands
+pub const PROFILE_VERSION: u32 = 1;
+
+#[derive(Debug, Fail)]
+pub enum ProfileError {
+    #[fail(display = "selector profile version {} is newer than supported version {}", found, supported)]
+    Version { found: u32, supported: u32 },
+
+    #[fail(display = "invalid selector for {}: {} ({})", field, selector, message)]
+    Selector { field: String, selector: String, message: String },
+
+    #[fail(display = "no selectors for {}", field)]
+    Empty { field: String },
+}
+
+/// Candidate CSS selectors for each scraped field, tried in order
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Selectors {
+    pub linked_data: Vec<String>,
+    pub poster: Vec<String>,
+    pub image: Vec<String>,
+    pub summary: Vec<String>,
+    pub synopsis: Vec<String>,
+    pub text: Vec<String>,
+    pub rating: Vec<String>,
+    pub score_value: Vec<String>,
+    pub score_count: Vec<String>,
+}
+
+/// Post-processing patterns applied to scraped text and links
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Regexes {
+    /// Inline links in summaries; parenthesized links are dropped, others replaced by their text
+    pub hyperlink: String,
+    /// Author credit trailing a summary
+    pub written_by: String,
+    /// Size suffix of an image URL, replaced to request the full-size image
+    pub resize: String,
+}
+
+/// Selector profile as stored in a JSON file
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ProfileSource {
+    pub version: u32,
+    pub selectors: Selectors,
+    pub regexes: Regexes,
+}
+
+impl Default for ProfileSource {
+    /// Matches the IMDB layout this crate was last updated for
+    fn default() -> Self {
+        let list = |selectors: &[&str]| selectors.iter().map(|&selector| selector.to_owned()).collect();
+        ProfileSource {
+            version: PROFILE_VERSION,
+            selectors: Selectors {
+                linked_data: list(&["script[type=\"application/ld+json\"]"]),
+                poster: list(&[".poster a[href]"]),
+                image: list(&["meta[property=\"og:image\"][content]"]),
+                summary: list(&["#titleStoryLine [itemprop=description] p"]),
+                synopsis: list(&["#titleStoryLine .see-more a[href]"]),
+                text: list(&["#plot-synopsis-content .ipl-zebra-list__item"]),
+                rating: list(&["meta[itemprop=contentRating][content]"]),
+                score_value: list(&["span[itemprop=ratingValue]"]),
+                score_count: list(&["span[itemprop=ratingCount]"]),
+            },
+            regexes: Regexes {
+                hyperlink: r"(\(\s*)?<a[^>]*>([^<]*)</a>(?:\s*\)\s*)?".to_owned(),
+                written_by: r"(?s:\s*<em.*>\s*)".to_owned(),
+                resize: r"@\._V1_.*\.jpg".to_owned(),
+            },
+        }
+    }
+}
+
+/// One compiled candidate selector
+pub struct Candidate {
+    pub source: String,
+    pub selector: Selector,
+}
+
+/// Compiled candidate selectors for one field
+pub struct Field {
+    pub name: &'static str,
+    pub candidates: Vec<Candidate>,
+}
+
+impl Field {
+    fn compile(name: &'static str, sources: &[String]) -> Result<Self, Error> {
+        if sources.is_empty() {
+            Err(ProfileError::Empty { field: name.to_owned() })?
+        }
+
+        let mut candidates = Vec::new();
+        for source in sources {
+            let selector = Selector::parse(source).map_err(|error| ProfileError::Selector {
+                field: name.to_owned(),
+                selector: source.clone(),
+                message: format!("{:?}", error.kind),
+            })?;
+            candidates.push(Candidate { source: source.clone(), selector });
+        }
+        Ok(Field { name, candidates })
+    }
+
+    /// Returns the first value [extract] finds under any candidate, in order,
+    /// along with the candidate that found it.
+    pub fn find<'a, T, F>(&'a self, html: &Html, mut extract: F) -> Option<(T, &'a str)>
+        where F: FnMut(ElementRef) -> Option<T>
+    {
+        self.candidates.iter()
+            .filter_map(|candidate| {
+                html.select(&candidate.selector)
+                    .filter_map(&mut extract)
+                    .next()
+                    .map(|value| (value, candidate.source.as_str()))
+            })
+            .next()
+    }
+}
+
+/// Compiled selector profile used by [IMDB]
+pub struct Profile {
+    pub version: u32,
+    pub linked_data: Field,
+    pub poster: Field,
+    pub image: Field,
+    pub summary: Field,
+    pub synopsis: Field,
+    pub text: Field,
+    pub rating: Field,
+    pub score_value: Field,
+    pub score_count: Field,
+    pub hyperlink: Regex,
+    pub written_by: Regex,
+    pub resize: Regex,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile::compile(&ProfileSource::default()).expect("Invalid built-in selector profile")
+    }
+}
+
+impl Profile {
+    /// Loads and compiles the JSON profile at [path].
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let source: ProfileSource = serde_json::from_reader(File::open(path)?)?;
+        Profile::compile(&source)
+    }
+
+    pub fn compile(source: &ProfileSource) -> Result<Self, Error> {
+        if source.version > PROFILE_VERSION {
+            Err(ProfileError::Version { found: source.version, supported: PROFILE_VERSION })?
+        }
+
+        let selectors = &source.selectors;
+        Ok(Profile {
+            version: source.version,
+            linked_data: Field::compile("linked_data", &selectors.linked_data)?,
+            poster: Field::compile("poster", &selectors.poster)?,
+            image: Field::compile("image", &selectors.image)?,
+            summary: Field::compile("summary", &selectors.summary)?,
+            synopsis: Field::compile("synopsis", &selectors.synopsis)?,
+            text: Field::compile("text", &selectors.text)?,
+            rating: Field::compile("rating", &selectors.rating)?,
+            score_value: Field::compile("score_value", &selectors.score_value)?,
+            score_count: Field::compile("score_count", &selectors.score_count)?,
+            hyperlink: Regex::new(&source.regexes.hyperlink)?,
+            written_by: Regex::new(&source.regexes.written_by)?,
+            resize: Regex::new(&source.regexes.resize)?,
+        })
+    }
+}
@@ -0,0 +1,82 @@
+use failure::Error;
+
+use tmdb::TMDB;
+
+/// One line of a researcher-supplied movie list.
+#[derive(Debug, PartialEq)]
+pub enum Query {
+    /// IMDB ID such as `tt0111161`
+    IMDB(String),
+    /// Title with optional release year, written `Title (1994)` or `Title<TAB>1994`
+    Title { title: String, year: Option<i32> },
+}
+
+impl Query {
+    /// Parses [line], returning `None` for blank lines and `#` comments.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            return None
+        }
+
+        if line.starts_with("tt") && line.len() > 2 && line[2..].chars().all(|c| c.is_ascii_digit()) {
+            return Some(Query::IMDB(line.to_owned()))
+        }
+
+        if let Some(tab) = line.rfind('\t') {
+            if let Ok(year) = line[tab + 1..].trim().parse() {
+                return Some(Query::Title { title: line[..tab].trim().to_owned(), year: Some(year) })
+            }
+        }
+
+        if line.ends_with(')') {
+            if let Some(open) = line.rfind('(') {
+                if let Ok(year) = line[open + 1..line.len() - 1].trim().parse() {
+                    return Some(Query::Title { title: line[..open].trim().to_owned(), year: Some(year) })
+                }
+            }
+        }
+
+        Some(Query::Title { title: line.to_owned(), year: None })
+    }
+
+    /// Returns the TMDB ID [self] refers to, or `None` if there is no confident match.
+    /// Titles only resolve to a candidate with the same title, released within a year of
+    /// the given year.
+    pub fn resolve(&self, tmdb: &TMDB) -> Result<Option<i32>, Error> {
+        match *self {
+        | Query::IMDB(ref id) => tmdb.find_by_imdb_id(id),
+        | Query::Title { ref title, year } => {
+            Ok(
+                tmdb.search_movie(title, year)?
+                    .into_iter()
+                    .find(|candidate| candidate.matches(title) && candidate.released_near(year))
+                    .map(|candidate| candidate.id)
+            )
+        }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn title(title: &str, year: Option<i32>) -> Option<Query> {
+        Some(Query::Title { title: title.to_owned(), year })
+    }
+
+    #[test]
+    fn parse_lines() {
+        assert_eq!(Query::parse("  "), None);
+        assert_eq!(Query::parse("# researcher list"), None);
+        assert_eq!(Query::parse(" tt0111161 "), Some(Query::IMDB("tt0111161".to_owned())));
+        assert_eq!(Query::parse("tt"), title("tt", None));
+        assert_eq!(Query::parse("ttx123"), title("ttx123", None));
+        assert_eq!(Query::parse("The Shawshank Redemption (1994)"), title("The Shawshank Redemption", Some(1994)));
+        assert_eq!(Query::parse("The Shawshank Redemption\t1994"), title("The Shawshank Redemption", Some(1994)));
+        assert_eq!(Query::parse("Alien (Director's Cut)"), title("Alien (Director's Cut)", None));
+        assert_eq!(Query::parse("Se7en"), title("Se7en", None));
+    }
+}
@@ -0,0 +1,204 @@
+use failure::Error;
+use rand::random;
+use reqwest;
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+use fetch::*;
+use tmdb::TMDBError;
+
+/// Whether a failed request is worth sending again.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Class {
+    /// Transient: timeouts, connection resets, 5xx and 429 responses
+    Retryable,
+    /// Permanent: 404s, bad credentials, malformed responses, missing page elements
+    Fatal,
+}
+
+/// Sorts [error] into [Class::Retryable] or [Class::Fatal].
+pub fn classify(error: &Error) -> Class {
+    if let Some(error) = error.downcast_ref::<FetchError>() {
+        match *error {
+        | FetchError::Status { status } => classify_status(status),
+        }
+    } else if let Some(error) = error.downcast_ref::<TMDBError>() {
+        match *error {
+        | TMDBError::RateLimited { .. } | TMDBError::Server { .. } => Class::Retryable,
+        | TMDBError::Http { status, .. } => classify_status(status),
+        | _ => Class::Fatal,
+        }
+    } else if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+        match error.status() {
+        | Some(status) => classify_status(status.as_u16()),
+        | None if error.is_serialization() || error.is_redirect() => Class::Fatal,
+        | None => Class::Retryable,
+        }
+    } else if let Some(error) = error.downcast_ref::<io::Error>() {
+        match error.kind() {
+        | io::ErrorKind::TimedOut
+        | io::ErrorKind::Interrupted
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::ConnectionRefused
+        | io::ErrorKind::BrokenPipe
+        | io::ErrorKind::UnexpectedEof => Class::Retryable,
+        | _ => Class::Fatal,
+        }
+    } else {
+        Class::Fatal
+    }
+}
+
+/// Sorts HTTP status [status] into [Class::Retryable] or [Class::Fatal].
+pub fn classify_status(status: u16) -> Class {
+    match status {
+    | 408 | 429 | 500..=599 => Class::Retryable,
+    | _ => Class::Fatal,
+    }
+}
+
+/// Retry budget for one source, with jittered exponential backoff between attempts.
+#[derive(Copy, Clone, Debug)]
+pub struct Backoff {
+    /// Number of attempts after the first
+    pub retries: u32,
+    /// Upper bound of the first delay; doubled for each further attempt
+    pub base: Duration,
+    /// Upper bound of any single delay
+    pub limit: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            retries: 3,
+            base: Duration::from_millis(500),
+            limit: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Backoff {
+    /// Never retries.
+    pub fn none() -> Self {
+        Backoff { retries: 0, ..Backoff::default() }
+    }
+
+    /// Runs [attempt] until it succeeds, fails fatally, or the budget runs out.
+    pub fn run<T, F>(&self, mut attempt: F) -> Result<T, Error> where F: FnMut() -> Result<T, Error> {
+        let mut retries = 0;
+        loop {
+            match attempt() {
+            | Err(ref error) if retries < self.retries && classify(error) == Class::Retryable => {
+                let delay = self.delay(retries);
+                warn!("[RETRY] {}; attempt {} of {} in {:?}", error, retries + 1, self.retries, delay);
+                thread::sleep(delay);
+                retries += 1;
+            }
+            | result => return result,
+            }
+        }
+    }
+
+    /// Fetches [url], treating retryable statuses as errors and passing all others through.
+    pub fn fetch(&self, fetcher: &dyn Fetcher, url: &str) -> Result<Response, Error> {
+        self.run(|| {
+            let response = fetcher.fetch(url)?;
+            if classify_status(response.status) == Class::Retryable {
+                Err(FetchError::Status { status: response.status })?
+            }
+            Ok(response)
+        })
+    }
+
+    /// Picks a delay uniformly between zero and the ceiling for attempt [retry].
+    fn delay(&self, retry: u32) -> Duration {
+        self.base
+            .checked_mul(1 << retry.min(16))
+            .map_or(self.limit, |ceiling| ceiling.min(self.limit))
+            .mul_f64(random::<f64>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_errors() {
+        let retryable: Vec<Error> = vec![
+            FetchError::Status { status: 503 }.into(),
+            FetchError::Status { status: 408 }.into(),
+            TMDBError::RateLimited { message: String::new() }.into(),
+            TMDBError::Server { status: 502, message: String::new() }.into(),
+            TMDBError::Http { status: 429, message: String::new() }.into(),
+            io::Error::from(io::ErrorKind::TimedOut).into(),
+            io::Error::from(io::ErrorKind::ConnectionReset).into(),
+        ];
+        for error in &retryable {
+            assert_eq!(classify(error), Class::Retryable, "{}", error);
+        }
+
+        let fatal: Vec<Error> = vec![
+            FetchError::Status { status: 404 }.into(),
+            TMDBError::NotFound { message: String::new() }.into(),
+            TMDBError::Unauthorized { message: String::new() }.into(),
+            TMDBError::Http { status: 400, message: String::new() }.into(),
+            io::Error::from(io::ErrorKind::NotFound).into(),
+            format_err!("missing poster"),
+        ];
+        for error in &fatal {
+            assert_eq!(classify(error), Class::Fatal, "{}", error);
+        }
+    }
+
+    #[test]
+    fn delays_stay_under_ceiling() {
+        let backoff = Backoff {
+            retries: 20,
+            base: Duration::from_millis(100),
+            limit: Duration::from_secs(1),
+        };
+        for _ in 0..100 {
+            assert!(backoff.delay(0) <= Duration::from_millis(100));
+            assert!(backoff.delay(2) <= Duration::from_millis(400));
+            assert!(backoff.delay(10) <= Duration::from_secs(1));
+            assert!(backoff.delay(40) <= Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn run_retries_transient_failures_only() {
+        let backoff = Backoff {
+            retries: 2,
+            base: Duration::from_millis(1),
+            limit: Duration::from_millis(1),
+        };
+
+        let mut attempts = 0;
+        let result = backoff.run(|| {
+            attempts += 1;
+            if attempts < 3 { Err(FetchError::Status { status: 503 })? }
+            Ok(attempts)
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        let mut attempts = 0;
+        let result: Result<(), Error> = backoff.run(|| {
+            attempts += 1;
+            Err(FetchError::Status { status: 503 })?
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+
+        let mut attempts = 0;
+        let result: Result<(), Error> = backoff.run(|| {
+            attempts += 1;
+            Err(FetchError::Status { status: 404 })?
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+}
@@ -1,13 +1,24 @@
-use reqwest::get;
-use serde_json::from_str;
+use serde_json::{from_reader, from_str};
 use failure::Error;
-use std::{thread, time, env};
+use reqwest::Url;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering as Atomic};
+use std::time::Duration;
 
+use fetch::*;
+use limit::Limiter;
+use retry::*;
 use movie::*;
+use tv::*;
 
 #[derive(Deserialize)]
 struct MovieID {
-    pub id: i32
+    pub id: i32,
 }
 
 #[derive(Deserialize)]
@@ -21,18 +32,61 @@ pub struct Genre {
     pub name: String,
 }
 
+#[derive(Deserialize, Serialize)]
+pub struct Collection {
+    pub id: i32,
+    pub name: String,
+    pub poster_path: Option<String>,
+    pub backdrop_path: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Company {
+    pub id: i32,
+    pub name: String,
+    pub logo_path: Option<String>,
+    pub origin_country: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Country {
+    pub iso_3166_1: String,
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SpokenLanguage {
+    pub iso_639_1: String,
+    pub name: String,
+}
+
 #[derive(Deserialize)]
 pub struct RawMovie {
     pub id: i32,
     pub imdb_id: String,
     pub title: String,
+    pub original_title: Option<String>,
+    pub tagline: Option<String>,
     pub adult: bool,
+    pub belongs_to_collection: Option<Collection>,
+    pub budget: f32,
     pub genres: Vec<Genre>,
+    pub homepage: Option<String>,
     pub original_language: String,
     pub overview: Option<String>,
+    pub poster_path: Option<String>,
+    pub backdrop_path: Option<String>,
+    pub popularity: f32,
+    #[serde(default)]
+    pub production_companies: Vec<Company>,
+    #[serde(default)]
+    pub production_countries: Vec<Country>,
     pub release_date: String,
     pub revenue: f32,
     pub runtime: i32, 
+    #[serde(default)]
+    pub spoken_languages: Vec<SpokenLanguage>,
+    pub status: Option<String>,
     pub vote_average: f32,
     pub vote_count: i32,
 }
@@ -44,69 +98,843 @@ struct Keyword {
 
 #[derive(Deserialize)]
 struct Keywords {
+    /// TV keywords come back as `results`
+    #[serde(alias = "results")]
     pub keywords: Vec<Keyword>
 }
 
+#[derive(Deserialize)]
+struct ReleaseDate {
+    pub certification: String,
+    pub release_date: String,
+    #[serde(rename = "type")]
+    pub kind: i32,
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CountryReleases {
+    pub iso_3166_1: String,
+    pub release_dates: Vec<ReleaseDate>,
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReleaseType {
+    Premiere,
+    TheatricalLimited,
+    Theatrical,
+    Digital,
+    Physical,
+    #[serde(rename = "tv")]
+    TV,
+    Unknown,
+}
+
+impl From<i32> for ReleaseType {
+    fn from(kind: i32) -> Self {
+        match kind {
+        | 1 => ReleaseType::Premiere,
+        | 2 => ReleaseType::TheatricalLimited,
+        | 3 => ReleaseType::Theatrical,
+        | 4 => ReleaseType::Digital,
+        | 5 => ReleaseType::Physical,
+        | 6 => ReleaseType::TV,
+        | _ => ReleaseType::Unknown,
+        }
+    }
+}
+
+/// One release of a movie in one country
+#[derive(Deserialize, Serialize)]
+pub struct Release {
+    #[serde(rename = "type")]
+    pub kind: ReleaseType,
+    pub date: String,
+    /// Local content rating, e.g. `PG-13` in the US or `12A` in the UK
+    pub certification: Option<String>,
+    pub note: Option<String>,
+}
+
+/// Releases keyed by ISO 3166-1 country code
+pub type Releases = BTreeMap<String, Vec<Release>>;
+
+/// Returns the certification of the US theatrical release, falling back to any other US release.
+pub fn us_certification(releases: &Releases) -> Option<String> {
+    let us = releases.get("US")?;
+    [ReleaseType::Theatrical, ReleaseType::TheatricalLimited]
+        .iter()
+        .filter_map(|&kind| us.iter().find(|release| release.kind == kind && release.certification.is_some()))
+        .chain(us.iter().filter(|release| release.certification.is_some()))
+        .next()
+        .and_then(|release| release.certification.clone())
+}
+
+#[derive(Deserialize)]
+struct ReleaseDates {
+    pub results: Vec<CountryReleases>,
+}
+
+#[derive(Deserialize)]
+struct RawBundle {
+    #[serde(flatten)]
+    movie: RawMovie,
+    credits: Credits,
+    keywords: Keywords,
+    release_dates: ReleaseDates,
+    translations: Option<Translations>,
+    alternative_titles: Option<AlternativeTitles>,
+    videos: Option<Videos>,
+}
+
+/// Movie details, credits, keywords, release dates and, if asked for, translations,
+/// alternative titles and trailers from a single request.
+pub struct MovieBundle {
+    pub movie: RawMovie,
+    pub cast: Vec<Cast>,
+    pub crew: Departments,
+    pub keywords: Vec<String>,
+    pub releases: Releases,
+    /// Empty unless localized
+    pub translations: Localizations,
+    /// Empty unless localized
+    pub alternative_titles: BTreeMap<String, Vec<String>>,
+    /// Official trailers; empty unless asked for
+    pub trailers: Vec<Video>,
+}
+
+#[derive(Deserialize)]
+struct TranslationData {
+    pub title: Option<String>,
+    pub overview: Option<String>,
+    pub tagline: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Translation {
+    pub iso_639_1: String,
+    pub iso_3166_1: String,
+    pub data: TranslationData,
+}
+
+#[derive(Deserialize)]
+struct Translations {
+    pub translations: Vec<Translation>,
+}
+
+/// Title, overview and tagline in one language
+#[derive(Deserialize, Serialize)]
+pub struct Localized {
+    pub title: Option<String>,
+    pub overview: Option<String>,
+    pub tagline: Option<String>,
+}
+
+/// Localized text keyed by ISO 639-1 and ISO 3166-1 codes, e.g. `pt-BR`
+pub type Localizations = BTreeMap<String, Localized>;
+
+#[derive(Deserialize)]
+struct AlternativeTitle {
+    pub iso_3166_1: String,
+    pub title: String,
+}
+
+#[derive(Deserialize)]
+struct AlternativeTitles {
+    pub titles: Vec<AlternativeTitle>,
+}
+
+/// Trailer, teaser, clip or other video hosted on an external site
+#[derive(Deserialize, Serialize)]
+pub struct Video {
+    pub name: String,
+    /// Hosting site, e.g. `YouTube` or `Vimeo`
+    pub site: String,
+    /// Video ID on [site]
+    pub key: String,
+    /// e.g. `Trailer`, `Teaser` or `Clip`
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub official: bool,
+    pub iso_639_1: String,
+    pub published_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Videos {
+    pub results: Vec<Video>,
+}
+
+#[derive(Deserialize)]
+struct ExternalIDs {
+    pub imdb_id: Option<String>,
+}
+
+/// Creator of a TV show
+#[derive(Deserialize, Serialize)]
+pub struct Creator {
+    /// TMDB person ID
+    pub id: i32,
+    pub name: String,
+    #[serde(default)]
+    pub gender: Gender,
+    pub profile_path: Option<String>,
+}
+
+/// Season listed on a TV show, without its episodes
+#[derive(Deserialize)]
+pub struct SeasonSummary {
+    pub season_number: i32,
+    pub episode_count: i32,
+}
+
+#[derive(Deserialize)]
+pub struct RawShow {
+    pub id: i32,
+    pub name: String,
+    pub original_name: Option<String>,
+    pub tagline: Option<String>,
+    #[serde(default)]
+    pub adult: bool,
+    #[serde(default)]
+    pub created_by: Vec<Creator>,
+    #[serde(default)]
+    pub episode_run_time: Vec<i32>,
+    pub first_air_date: Option<String>,
+    pub last_air_date: Option<String>,
+    pub genres: Vec<Genre>,
+    pub homepage: Option<String>,
+    #[serde(default)]
+    pub in_production: bool,
+    #[serde(default)]
+    pub networks: Vec<Company>,
+    pub number_of_episodes: i32,
+    pub number_of_seasons: i32,
+    #[serde(default)]
+    pub origin_country: Vec<String>,
+    pub original_language: String,
+    pub overview: Option<String>,
+    pub poster_path: Option<String>,
+    pub backdrop_path: Option<String>,
+    pub popularity: f32,
+    #[serde(default)]
+    pub production_companies: Vec<Company>,
+    #[serde(default)]
+    pub production_countries: Vec<Country>,
+    #[serde(default)]
+    pub seasons: Vec<SeasonSummary>,
+    #[serde(default)]
+    pub spoken_languages: Vec<SpokenLanguage>,
+    pub status: Option<String>,
+    /// e.g. `Scripted`, `Miniseries` or `Reality`
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub vote_average: f32,
+    pub vote_count: i32,
+}
+
+#[derive(Deserialize)]
+struct ContentRating {
+    pub iso_3166_1: String,
+    pub rating: String,
+}
+
+#[derive(Deserialize)]
+struct ContentRatings {
+    pub results: Vec<ContentRating>,
+}
+
+#[derive(Deserialize)]
+struct RawShowBundle {
+    #[serde(flatten)]
+    show: RawShow,
+    credits: Credits,
+    keywords: Keywords,
+    external_ids: ExternalIDs,
+    content_ratings: ContentRatings,
+}
+
+/// Show details, credits, keywords, external IDs and content ratings from a single request.
+pub struct ShowBundle {
+    pub show: RawShow,
+    pub imdb_id: Option<String>,
+    pub cast: Vec<Cast>,
+    pub crew: Departments,
+    pub keywords: Vec<String>,
+    /// Content rating by ISO 3166-1 country code
+    pub ratings: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct RawEpisode {
+    pub id: i32,
+    pub name: String,
+    pub overview: Option<String>,
+    pub air_date: Option<String>,
+    pub episode_number: i32,
+    pub runtime: Option<i32>,
+    pub still_path: Option<String>,
+    #[serde(default)]
+    pub vote_average: f32,
+    #[serde(default)]
+    pub vote_count: i32,
+    #[serde(default)]
+    pub crew: Vec<Crew>,
+    #[serde(default)]
+    pub guest_stars: Vec<Cast>,
+}
+
+#[derive(Deserialize)]
+struct RawSeason {
+    pub id: i32,
+    pub name: String,
+    pub overview: Option<String>,
+    pub air_date: Option<String>,
+    pub season_number: i32,
+    pub poster_path: Option<String>,
+    pub episodes: Vec<RawEpisode>,
+}
+
+/// Where TMDB serves images from, and in which sizes
+#[derive(Deserialize, Clone)]
+pub struct ImageConfig {
+    pub secure_base_url: String,
+    pub poster_sizes: Vec<String>,
+    pub backdrop_sizes: Vec<String>,
+    pub profile_sizes: Vec<String>,
+}
+
+impl ImageConfig {
+    /// Returns the URL of image [path] at [size], or at its original size if [sizes] lacks [size].
+    pub fn url(&self, sizes: &[String], size: &str, path: &str) -> String {
+        let size = if sizes.iter().any(|available| available == size) {
+            size
+        } else {
+            warn!("[IMAGES] size {} unavailable; using original", size);
+            "original"
+        };
+        format!("{}{}{}", self.secure_base_url, size, path)
+    }
+}
+
+#[derive(Deserialize)]
+struct Configuration {
+    pub images: ImageConfig,
+}
+
+/// Biographical details of a cast or crew member
+#[derive(Deserialize, Serialize)]
+pub struct Person {
+    pub id: i32,
+    pub name: String,
+    pub imdb_id: Option<String>,
+    #[serde(default)]
+    pub also_known_as: Vec<String>,
+    pub biography: Option<String>,
+    pub birthday: Option<String>,
+    pub deathday: Option<String>,
+    pub place_of_birth: Option<String>,
+    #[serde(default)]
+    pub gender: Gender,
+    pub known_for_department: Option<String>,
+    pub profile_path: Option<String>,
+    pub popularity: Option<f32>,
+}
+
+/// Movie returned by TMDB's search and find endpoints
+#[derive(Deserialize, Clone)]
+pub struct Candidate {
+    pub id: i32,
+    pub title: String,
+    pub original_title: Option<String>,
+    pub release_date: Option<String>,
+    pub popularity: f32,
+}
+
+impl Candidate {
+    /// Returns the release year, if known
+    pub fn year(&self) -> Option<i32> {
+        self.release_date.as_ref()
+            .and_then(|date| date.get(0..4))
+            .and_then(|year| year.parse().ok())
+    }
+
+    /// Returns true if [year] is unknown, or within a year of the release year
+    pub fn released_near(&self, year: Option<i32>) -> bool {
+        match (year, self.year()) {
+        | (None, _) => true,
+        | (Some(year), Some(released)) => (year - released).abs() <= 1,
+        | (Some(_), None) => false,
+        }
+    }
+
+    /// Returns true if [title] matches either title, ignoring case and punctuation
+    pub fn matches(&self, title: &str) -> bool {
+        let title = normalize(title);
+        normalize(&self.title) == title
+            || self.original_title.iter().any(|original| normalize(original) == title)
+    }
+}
+
+#[derive(Deserialize)]
+struct Found {
+    pub movie_results: Vec<Candidate>,
+}
+
+#[derive(Deserialize)]
+struct Search {
+    pub results: Vec<Candidate>,
+}
+
 #[derive(Deserialize)]
 struct Page {
     pub total_pages: i32,
     pub results: Vec<MovieID>,
 }
 
+/// Error body returned by TMDB alongside non-2xx statuses
+#[derive(Deserialize)]
+struct Status {
+    pub status_code: i32,
+    pub status_message: String,
+}
+
 #[derive(Debug, Fail)]
 pub enum TMDBError {
-    #[fail(display = "{} is an adult movie", name)]
-    Adult { name: String },
+    #[fail(display = "no TMDB credentials; set TMDB_API_KEY or TMDB_BEARER_TOKEN, or pass a config file")]
+    Credentials,
+
+    #[fail(display = "not found on TMDB: {}", message)]
+    NotFound { message: String },
+
+    #[fail(display = "unauthorized by TMDB: {}", message)]
+    Unauthorized { message: String },
+
+    #[fail(display = "rate limited by TMDB: {}", message)]
+    RateLimited { message: String },
+
+    #[fail(display = "TMDB server error {}: {}", status, message)]
+    Server { status: u16, message: String },
+
+    #[fail(display = "unexpected TMDB response {}: {}", status, message)]
+    Http { status: u16, message: String },
+}
+
+impl TMDBError {
+    /// Builds the error for non-2xx [response] from its HTTP status and TMDB's error body.
+    fn from_response(response: &Response) -> Self {
+        let message = match from_str::<Status>(&response.text()) {
+        | Ok(body) => format!("{} (code {})", body.status_message, body.status_code),
+        | Err(_) => format!("no error body (HTTP {})", response.status),
+        };
+
+        match response.status {
+        | 401 => TMDBError::Unauthorized { message },
+        | 404 => TMDBError::NotFound { message },
+        | 429 => TMDBError::RateLimited { message },
+        | status @ 500..=599 => TMDBError::Server { status, message },
+        | status => TMDBError::Http { status, message },
+        }
+    }
+}
 
-    #[fail(display = "{}: is missing runtime", name)]
-    Runtime { name: String },
+/// Decides which credits [TMDB::get_people] keeps.
+pub struct CreditFilter {
+    /// Keep every crew member, ignoring [jobs] and [departments]
+    pub all_crew: bool,
+    /// Crew jobs to keep, e.g. `Director`
+    pub jobs: HashSet<String>,
+    /// Crew departments to keep in full, e.g. `Camera`
+    pub departments: HashSet<String>,
+    /// Number of top-billed cast members to keep; `None` keeps everyone
+    pub cast_limit: Option<usize>,
+}
+
+impl Default for CreditFilter {
+    /// Keeps the top 20 billed cast members and the crew in key creative roles
+    fn default() -> Self {
+        CreditFilter {
+            all_crew: false,
+            jobs: ["Director", "Producer", "Writer", "Screenplay", "Original Music Composer"]
+                .iter()
+                .map(|&job| job.to_owned())
+                .collect(),
+            departments: HashSet::new(),
+            cast_limit: Some(20),
+        }
+    }
+}
+
+impl CreditFilter {
+    /// Keeps every credit.
+    pub fn all() -> Self {
+        CreditFilter {
+            all_crew: true,
+            jobs: HashSet::new(),
+            departments: HashSet::new(),
+            cast_limit: None,
+        }
+    }
+
+    pub fn keeps(&self, crew: &Crew) -> bool {
+        self.all_crew || self.jobs.contains(&crew.job) || self.departments.contains(&crew.department)
+    }
+
+    /// Returns the kept cast in billing order, and the kept crew grouped by department.
+    fn apply(&self, mut credits: Credits) -> (Vec<Cast>, Departments) {
+        credits.cast.retain(|cast| !cast.character.is_empty() && !cast.name.is_empty());
+        credits.cast.sort_by_key(|cast| cast.order);
+        if let Some(limit) = self.cast_limit {
+            credits.cast.truncate(limit);
+        }
+
+        let crew = by_department(credits.crew.into_iter().filter(|crew| self.keeps(crew)));
+        (credits.cast, crew)
+    }
+}
+
+/// Root of the live TMDB v3 API
+pub const TMDB_BASE: &str = "https://api.themoviedb.org/3";
+
+/// Default request rate: one request every 300 milliseconds
+pub const TMDB_RATE: f64 = 1000.0 / 300.0;
+
+/// Delay used when a 429 response carries no `Retry-After` header
+const RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Last page TMDB will serve for any paginated endpoint
+const MAX_PAGE: i32 = 500;
+
+/// Ranked movie lists that can seed a crawl.
+pub enum List {
+    Popular,
+    TopRated,
+    NowPlaying,
+    Upcoming,
+    Discover(Discover),
+}
+
+/// Filters for the discover endpoint; `None` fields are left unfiltered.
+#[derive(Default)]
+pub struct Discover {
+    /// Earliest primary release year, inclusive
+    pub from_year: Option<i32>,
+    /// Latest primary release year, inclusive
+    pub to_year: Option<i32>,
+    /// TMDB genre ID
+    pub genre: Option<i32>,
+    pub min_votes: Option<i32>,
+    /// ISO 639-1 code
+    pub original_language: Option<String>,
+}
+
+impl List {
+    /// Returns the endpoint path and extra query parameters of this list
+    fn endpoint(&self) -> (&'static str, String) {
+        match *self {
+        | List::Popular => ("/movie/popular", String::new()),
+        | List::TopRated => ("/movie/top_rated", String::new()),
+        | List::NowPlaying => ("/movie/now_playing", String::new()),
+        | List::Upcoming => ("/movie/upcoming", String::new()),
+        | List::Discover(ref discover) => {
+            let mut params = String::from("&sort_by=popularity.desc&include_adult=false&include_video=false");
+            if let Some(year) = discover.from_year {
+                params.push_str(&format!("&primary_release_date.gte={}-01-01", year));
+            }
+            if let Some(year) = discover.to_year {
+                params.push_str(&format!("&primary_release_date.lte={}-12-31", year));
+            }
+            if let Some(genre) = discover.genre {
+                params.push_str(&format!("&with_genres={}", genre));
+            }
+            if let Some(votes) = discover.min_votes {
+                params.push_str(&format!("&vote_count.gte={}", votes));
+            }
+            if let Some(ref language) = discover.original_language {
+                params.push_str(&format!("&with_original_language={}", language));
+            }
+            ("/discover/movie", params)
+        }
+        }
+    }
 }
 
-lazy_static! {
-    static ref DELAY: time::Duration = time::Duration::from_millis(300);
+/// One way of authenticating with TMDB.
+#[derive(Clone, Debug)]
+pub enum Credential {
+    /// v3 API key, sent as the `api_key` query parameter
+    Key(String),
+    /// v4 read access token, sent in the `Authorization` header
+    Bearer(String),
+}
+
+/// Every credential a [TMDB] connection may rotate through, e.g. read from a JSON
+/// config file of the form `{"keys": ["KEY_0", "KEY_1"], "tokens": ["TOKEN_0"]}`.
+#[derive(Deserialize, Default)]
+pub struct Credentials {
+    /// v3 API keys
+    #[serde(default)]
+    pub keys: Vec<String>,
+    /// v4 read access tokens
+    #[serde(default)]
+    pub tokens: Vec<String>,
+}
+
+impl Credentials {
+    /// Reads comma-separated keys from `TMDB_API_KEY` and tokens from `TMDB_BEARER_TOKEN`.
+    pub fn from_env() -> Self {
+        let list = |name: &str| env::var(name)
+            .unwrap_or_default()
+            .split(',')
+            .map(|item| item.trim().to_owned())
+            .filter(|item| !item.is_empty())
+            .collect();
+
+        Credentials { keys: list("TMDB_API_KEY"), tokens: list("TMDB_BEARER_TOKEN") }
+    }
+
+    /// Reads the JSON config file at [path].
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(from_reader(File::open(path)?)?)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.tokens.is_empty()
+    }
+
+    /// Returns every key, then every token.
+    fn into_vec(self) -> Vec<Credential> {
+        self.keys.into_iter().map(Credential::Key)
+            .chain(self.tokens.into_iter().map(Credential::Bearer))
+            .collect()
+    }
+}
+
+/// A credential and the rate limit that applies to it alone
+struct Account {
+    credential: Credential,
+    limiter: Limiter,
+}
+
+/// Ranked TV lists that can seed a crawl.
+pub enum ShowList {
+    Popular,
+    TopRated,
+    OnTheAir,
+    AiringToday,
+}
+
+impl ShowList {
+    /// Returns the endpoint path of this list
+    fn path(&self) -> &'static str {
+        match *self {
+        | ShowList::Popular => "/tv/popular",
+        | ShowList::TopRated => "/tv/top_rated",
+        | ShowList::OnTheAir => "/tv/on_the_air",
+        | ShowList::AiringToday => "/tv/airing_today",
+        }
+    }
 }
 
 /// Represents a TMDB connection with API key and rate limiting.
+///
+/// All methods take `&self`, so one connection can be shared across threads
+/// and its rate limits will hold for all of them together. Requests rotate
+/// through the credentials round-robin, each credential with its own rate limit.
 pub struct TMDB {
-    last_query: time::Instant,
-    key: String,
+    accounts: Vec<Account>,
+    next: AtomicUsize,
+    retry: Backoff,
+    fetcher: Arc<dyn Fetcher>,
+    base: String,
+    language: String,
+    region: Option<String>,
 }
 
 impl Default for TMDB {
+    /// Panics unless the environment holds credentials; see [TMDB::from_env].
     fn default() -> Self {
-        TMDB {
-            last_query: time::Instant::now(),
-            key: env::var("TMDB_API_KEY").expect("Missing environment variable TMDB_API_KEY"),
-        }
+        TMDB::from_env().expect("Missing environment variable TMDB_API_KEY or TMDB_BEARER_TOKEN")
     }
 }
 
 impl TMDB {
-    pub fn query(&mut self, url: &str) -> Result<String, Error> {
-        let delta = time::Instant::now() - self.last_query;
-        if *DELAY > delta { thread::sleep(delta); }
-        let data = get(url)?.text()?;
-        self.last_query = time::Instant::now();
-        Ok(data)
+    /// Connects with the single v3 API key [key].
+    pub fn new(key: &str) -> Self {
+        TMDB::with_credential_list(vec![Credential::Key(key.to_owned())])
     }
 
-    pub fn get_latest(&mut self) -> Result<i32, Error> {
-        let url = format!(
-            "https://api.themoviedb.org/3/movie/latest?api_key={}&language=en-US",
-            &self.key,
-        );
+    /// Connects with every credential in [credentials], failing if there are none.
+    pub fn with_credentials(credentials: Credentials) -> Result<Self, Error> {
+        if credentials.is_empty() {
+            Err(TMDBError::Credentials)?
+        }
+        Ok(TMDB::with_credential_list(credentials.into_vec()))
+    }
+
+    /// Connects with the credentials in `TMDB_API_KEY` and `TMDB_BEARER_TOKEN`.
+    pub fn from_env() -> Result<Self, Error> {
+        TMDB::with_credentials(Credentials::from_env())
+    }
+
+    /// Connects with the credentials in the JSON config file at [path].
+    pub fn from_config<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        TMDB::with_credentials(Credentials::from_file(path)?)
+    }
+
+    fn with_credential_list(credentials: Vec<Credential>) -> Self {
+        TMDB {
+            accounts: credentials
+                .into_iter()
+                .map(|credential| Account { credential, limiter: Limiter::new(TMDB_RATE, 1) })
+                .collect(),
+            next: AtomicUsize::new(0),
+            retry: Backoff::default(),
+            fetcher: Arc::new(Reqwest::default()),
+            base: TMDB_BASE.to_owned(),
+            language: "en-US".to_owned(),
+            region: None,
+        }
+    }
+
+    /// Points all requests at [base] instead of the live API, e.g. a local fixture server.
+    pub fn with_base_url(mut self, base: &str) -> Self {
+        self.base = base.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Allows [rate] requests per second on average, and at most [burst] back to back,
+    /// for each credential. Panics unless [rate] is positive.
+    pub fn with_rate(mut self, rate: f64, burst: u32) -> Self {
+        for account in &mut self.accounts {
+            account.limiter = Limiter::new(rate, burst);
+        }
+        self
+    }
+
+    /// Retries transient failures, including 429 Too Many Requests, within budget [retry].
+    pub fn with_retry(mut self, retry: Backoff) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Sends all requests through [fetcher] instead of the network.
+    pub fn with_fetcher(mut self, fetcher: Arc<dyn Fetcher>) -> Self {
+        self.fetcher = fetcher;
+        self
+    }
+
+    /// Localizes responses in [language], an ISO 639-1 code optionally followed by
+    /// an ISO 3166-1 code, e.g. `fr` or `pt-BR`.
+    pub fn with_language(mut self, language: &str) -> Self {
+        self.language = language.to_owned();
+        self
+    }
+
+    /// Narrows release dates and lists to [region], an ISO 3166-1 code such as `FR`.
+    pub fn with_region(mut self, region: &str) -> Self {
+        self.region = Some(region.to_owned());
+        self
+    }
+
+    /// Returns the URL of API endpoint [path], relative to the configured base
+    fn url(&self, path: &str) -> String {
+        let mut url = format!("{}{}?language={}", self.base, path, self.language);
+        if let Some(ref region) = self.region {
+            url.push_str(&format!("&region={}", region));
+        }
+        url
+    }
+
+    /// Sends [url] with the next credential in turn, once its rate limit allows.
+    pub fn query(&self, url: &str) -> Result<String, Error> {
+        self.retry.run(|| {
+            let account = &self.accounts[self.next.fetch_add(1, Atomic::Relaxed) % self.accounts.len()];
+            account.limiter.acquire();
+
+            let response = match account.credential {
+            | Credential::Key(ref key) => self.fetcher.fetch(&format!("{}&api_key={}", url, key))?,
+            | Credential::Bearer(ref token) => self.fetcher.fetch_with_headers(
+                url,
+                &[("Authorization", &format!("Bearer {}", token))],
+            )?,
+            };
+
+            if response.status == 429 {
+                let delay = response.retry_after.unwrap_or(RETRY_AFTER);
+                warn!("[RATE LIMITED] pausing TMDB requests on this credential for {:?}", delay);
+                account.limiter.pause(delay);
+            }
+
+            if response.is_success() {
+                Ok(response.text())
+            } else {
+                Err(TMDBError::from_response(&response))?
+            }
+        })
+    }
+
+    /// Returns the TMDB ID of the movie with IMDB ID [imdb_id], if TMDB knows it.
+    pub fn find_by_imdb_id(&self, imdb_id: &str) -> Result<Option<i32>, Error> {
+        let url = format!("{}&external_source=imdb_id", self.url(&format!("/find/{}", imdb_id)));
+
+        let data = self.query(&url)?;
+        Ok(from_str::<Found>(&data)?.movie_results.first().map(|movie| movie.id))
+    }
+
+    /// Returns movies matching [title], best first: exact title matches, then
+    /// matches released in [year], then the most popular. Searches only movies
+    /// released in [year] if given, unless none match.
+    pub fn search_movie(&self, title: &str, year: Option<i32>) -> Result<Vec<Candidate>, Error> {
+        let mut candidates = match year {
+        | Some(year) => self.search(&[
+            ("query", title),
+            ("include_adult", "false"),
+            ("primary_release_year", &year.to_string()),
+        ])?,
+        | None => Vec::new(),
+        };
+        if candidates.is_empty() {
+            candidates = self.search(&[("query", title), ("include_adult", "false")])?;
+        }
+
+        candidates.sort_by(|a, b| {
+            let rank = |candidate: &Candidate| (candidate.matches(title), year.is_some() && candidate.year() == year);
+            rank(b).cmp(&rank(a))
+                .then(b.popularity.partial_cmp(&a.popularity).unwrap_or(Ordering::Equal))
+        });
+
+        Ok(candidates)
+    }
+
+    fn search(&self, params: &[(&str, &str)]) -> Result<Vec<Candidate>, Error> {
+        let url = Url::parse_with_params(&self.url("/search/movie"), params)?;
+
+        let data = self.query(url.as_str())?;
+        Ok(from_str::<Search>(&data)?.results)
+    }
+
+    /// Returns the image base URL and sizes, which TMDB asks clients to look up rather than hardcode.
+    pub fn get_configuration(&self) -> Result<ImageConfig, Error> {
+        let url = self.url("/configuration");
+
+        let data = self.query(&url)?;
+        Ok(from_str::<Configuration>(&data)?.images)
+    }
+
+    pub fn get_latest(&self) -> Result<i32, Error> {
+        let url = self.url("/movie/latest");
 
         let data = self.query(&url)?;
         let movie: MovieID = from_str(&data)?;
         Ok(movie.id)
     }
 
-    pub fn get_page(&mut self, page: i32) -> Result<Vec<i32>, Error> {
-        let url = format!(
-            "https://api.themoviedb.org/3/movie/popular?api_key={}&language=en-US&page={}",
-            &self.key,
-            page
-        );
+    /// Returns the IDs on page [page] of [list].
+    pub fn get_page(&self, list: &List, page: i32) -> Result<Vec<i32>, Error> {
+        let (path, params) = list.endpoint();
+        let url = format!("{}{}&page={}", self.url(path), params, page);
 
         let data = self.query(&url)?;
 
@@ -119,61 +947,381 @@ impl TMDB {
         )
     }
 
-    pub fn get_raw_movie(&mut self, id: i32) -> Result<RawMovie, Error> {
-        let url = format!(
-            "https://api.themoviedb.org/3/movie/{}?api_key={}&language=en-US",
-            id,
-            &self.key
-        );
+    /// Returns the IDs of the first [count] movies of [list], or of the whole list if it is shorter.
+    pub fn get_top(&self, list: &List, count: usize) -> Result<Vec<i32>, Error> {
+        let (path, params) = list.endpoint();
+        let url = format!("{}{}", self.url(path), params);
+
+        Ok(
+            self.paginate(&url, Some(count))?
+                .into_iter()
+                .map(|movie| movie.id)
+                .collect()
+        )
+    }
+
+    /// Returns the IDs of movies changed between [start] and [end], formatted `YYYY-MM-DD`.
+    /// TMDB accepts windows of at most 14 days.
+    pub fn get_changes(&self, start: &str, end: &str) -> Result<Vec<i32>, Error> {
+        let url = format!("{}&start_date={}&end_date={}", self.url("/movie/changes"), start, end);
+
+        Ok(
+            self.paginate(&url, None)?
+                .into_iter()
+                .map(|movie| movie.id)
+                .collect()
+        )
+    }
+
+    /// Collects the results of paginated endpoint [url], stopping after [limit] results if given.
+    fn paginate(&self, url: &str, limit: Option<usize>) -> Result<Vec<MovieID>, Error> {
+        let mut results = Vec::new();
+        let mut page = 1;
+        loop {
+            let data = self.query(&format!("{}&page={}", url, page))?;
+            let next = from_str::<Page>(&data)?;
+            results.extend(next.results);
+
+            if let Some(limit) = limit {
+                if results.len() >= limit {
+                    results.truncate(limit);
+                    return Ok(results)
+                }
+            }
+
+            if page >= next.total_pages.min(MAX_PAGE) { return Ok(results) }
+            page += 1;
+        }
+    }
+
+    /// Fetches everything [Movie::save] needs from TMDB in one request using `append_to_response`:
+    /// with translations and alternative titles if [localize], and with official trailers in
+    /// [trailers], ISO 639-1 codes, if given. An empty [trailers] gets trailers in the connection's
+    /// language only, as TMDB returns no others without `include_video_language`.
+    pub fn get_movie_bundle(&self, id: i32, filter: &CreditFilter, localize: bool, trailers: Option<&[String]>) -> Result<MovieBundle, Error> {
+        let mut append = vec!["credits", "keywords", "release_dates"];
+        if localize {
+            append.extend(&["translations", "alternative_titles"]);
+        }
+        if trailers.is_some() {
+            append.push("videos");
+        }
+
+        let mut url = format!("{}&append_to_response={}", self.url(&format!("/movie/{}", id)), append.join(","));
+        if let Some(languages) = trailers.filter(|languages| !languages.is_empty()) {
+            url.push_str(&format!("&include_video_language={}", languages.join(",")));
+        }
 
         let data = self.query(&url)?;
-        let movie: RawMovie = from_str(&data)?;
+        let bundle = from_str::<RawBundle>(&data)?;
+        let (cast, crew) = filter.apply(bundle.credits);
 
-        if movie.adult {
-            Err(TMDBError::Adult { name: movie.title })?
-        } else if movie.runtime == 0 {
-            Err(TMDBError::Runtime { name: movie.title })?
-        } else {
-            Ok(movie)
+        Ok(MovieBundle {
+            movie: bundle.movie,
+            cast,
+            crew,
+            keywords: keyword_names(bundle.keywords),
+            releases: group_releases(bundle.release_dates),
+            translations: bundle.translations.map(localizations).unwrap_or_default(),
+            alternative_titles: bundle.alternative_titles.map(group_titles).unwrap_or_default(),
+            trailers: match (bundle.videos, trailers) {
+            | (Some(videos), Some(languages)) => official_trailers(videos.results, languages),
+            | _ => Vec::new(),
+            },
+        })
+    }
+
+    pub fn get_raw_movie(&self, id: i32) -> Result<RawMovie, Error> {
+        let url = self.url(&format!("/movie/{}", id));
+
+        let data = self.query(&url)?;
+        Ok(from_str(&data)?)
+    }
+
+    /// Returns the releases of movie [id] in every country.
+    pub fn get_release_dates(&self, id: i32) -> Result<Releases, Error> {
+        let url = self.url(&format!("/movie/{}/release_dates", id));
+
+        let data = self.query(&url)?;
+        Ok(group_releases(from_str(&data)?))
+    }
+
+    /// Returns every translation of the title, overview and tagline of movie [id].
+    pub fn get_translations(&self, id: i32) -> Result<Localizations, Error> {
+        let url = self.url(&format!("/movie/{}/translations", id));
+
+        let data = self.query(&url)?;
+        Ok(localizations(from_str(&data)?))
+    }
+
+    /// Returns the alternative titles of movie [id], keyed by ISO 3166-1 country code.
+    pub fn get_alternative_titles(&self, id: i32) -> Result<BTreeMap<String, Vec<String>>, Error> {
+        let url = self.url(&format!("/movie/{}/alternative_titles", id));
+
+        let data = self.query(&url)?;
+        Ok(group_titles(from_str(&data)?))
+    }
+
+    /// Returns the videos of movie [id] in any of [languages], ISO 639-1 codes,
+    /// or in the connection's language if [languages] is empty.
+    pub fn get_videos(&self, id: i32, languages: &[String]) -> Result<Vec<Video>, Error> {
+        let mut url = self.url(&format!("/movie/{}/videos", id));
+        if !languages.is_empty() {
+            url.push_str(&format!("&include_video_language={}", languages.join(",")));
         }
+
+        let data = self.query(&url)?;
+        Ok(from_str::<Videos>(&data)?.results)
+    }
+
+    /// Returns the IMDB ID of movie [id], if it has one.
+    pub fn get_imdb_id(&self, id: i32) -> Result<Option<String>, Error> {
+        let url = self.url(&format!("/movie/{}/external_ids", id));
+
+        let data = self.query(&url)?;
+        Ok(from_str::<ExternalIDs>(&data)?.imdb_id.filter(|id| !id.is_empty()))
+    }
+
+    /// Returns up to [count] movies TMDB recommends to viewers of movie [id], best first.
+    pub fn get_recommendations(&self, id: i32, count: usize) -> Result<Vec<i32>, Error> {
+        let url = self.url(&format!("/movie/{}/recommendations", id));
+        Ok(self.paginate(&url, Some(count))?.into_iter().map(|movie| movie.id).collect())
     }
 
-    pub fn get_keywords(&mut self, id: i32) -> Result<Vec<String>, Error> {
+    /// Returns up to [count] movies with genres and keywords similar to movie [id], best first.
+    pub fn get_similar(&self, id: i32, count: usize) -> Result<Vec<i32>, Error> {
+        let url = self.url(&format!("/movie/{}/similar", id));
+        Ok(self.paginate(&url, Some(count))?.into_iter().map(|movie| movie.id).collect())
+    }
+
+    pub fn get_keywords(&self, id: i32) -> Result<Vec<String>, Error> {
+        let url = self.url(&format!("/movie/{}/keywords", id));
+
+        let data = self.query(&url)?;
+        Ok(keyword_names(from_str(&data)?))
+    }
+
+    /// Returns the details of the person with TMDB person ID [id].
+    pub fn get_person(&self, id: i32) -> Result<Person, Error> {
+        let url = self.url(&format!("/person/{}", id));
+
+        let data = self.query(&url)?;
+        Ok(from_str(&data)?)
+    }
+
+    pub fn get_people(&self, id: i32, filter: &CreditFilter) -> Result<(Vec<Cast>, Departments), Error> {
+        let url = self.url(&format!("/movie/{}/credits", id));
+
+        let data = self.query(&url)?;
+        Ok(filter.apply(from_str(&data)?))
+    }
+
+    /// Returns the IDs of the first [count] shows of [list], or of the whole list if it is shorter.
+    pub fn get_top_shows(&self, list: &ShowList, count: usize) -> Result<Vec<i32>, Error> {
+        let url = self.url(list.path());
+        Ok(self.paginate(&url, Some(count))?.into_iter().map(|show| show.id).collect())
+    }
+
+    /// Fetches everything [TvShow::save] needs about show [id] except its seasons,
+    /// using `append_to_response`.
+    pub fn get_show_bundle(&self, id: i32, filter: &CreditFilter) -> Result<ShowBundle, Error> {
         let url = format!(
-            "https://api.themoviedb.org/3/movie/{}/keywords?api_key={}&language=en-US",
-            id,
-            &self.key
+            "{}&append_to_response=credits,keywords,external_ids,content_ratings",
+            self.url(&format!("/tv/{}", id)),
         );
 
         let data = self.query(&url)?;
+        let bundle = from_str::<RawShowBundle>(&data)?;
+        let (cast, crew) = filter.apply(bundle.credits);
 
-        Ok(
-            from_str::<Keywords>(&data)?.keywords
+        Ok(ShowBundle {
+            show: bundle.show,
+            imdb_id: bundle.external_ids.imdb_id.filter(|id| !id.is_empty()),
+            cast,
+            crew,
+            keywords: keyword_names(bundle.keywords),
+            ratings: bundle.content_ratings.results
                 .into_iter()
-                .map(|word| word.name)
-                .collect()
-        )
+                .filter(|rating| !rating.rating.is_empty())
+                .map(|rating| (rating.iso_3166_1, rating.rating))
+                .collect(),
+        })
     }
 
-    pub fn get_people(&mut self, id: i32) -> Result<(Vec<Cast>, Vec<Crew>), Error> {
-        let url = format!(
-            "https://api.themoviedb.org/3/movie/{}/credits?api_key={}&language=en-US",
-            id,
-            &self.key
-        );
+    /// Returns season [number] of show [id] with every episode, keeping the
+    /// guest stars and crew allowed by [filter].
+    pub fn get_season(&self, id: i32, number: i32, filter: &CreditFilter) -> Result<Season, Error> {
+        let url = self.url(&format!("/tv/{}/season/{}", id, number));
 
         let data = self.query(&url)?;
-        let mut credits = from_str::<Credits>(&data)?;
+        let season = from_str::<RawSeason>(&data)?;
 
-        credits.crew.retain(|crew| {
-            match crew.job.as_ref() {
-            | "Director" | "Producer" | "Writer" | "Screenplay" | "Original Music Composer" => true,
-            | _ => false,
-            }                
-        });
+        Ok(Season {
+            tmdb_id: season.id,
+            season_number: season.season_number,
+            name: season.name,
+            overview: season.overview.filter(|text| !text.is_empty()),
+            air_date: season.air_date,
+            poster_path: season.poster_path,
+            episodes: season.episodes
+                .into_iter()
+                .map(|episode| {
+                    let (guest_stars, crew) = filter.apply(Credits { cast: episode.guest_stars, crew: episode.crew });
+                    Episode {
+                        tmdb_id: episode.id,
+                        episode_number: episode.episode_number,
+                        name: episode.name,
+                        overview: episode.overview.filter(|text| !text.is_empty()),
+                        air_date: episode.air_date,
+                        runtime: episode.runtime,
+                        still_path: episode.still_path,
+                        guest_stars,
+                        crew,
+                        tmdb_score_value: episode.vote_average,
+                        tmdb_score_count: episode.vote_count,
+                    }
+                })
+                .collect(),
+        })
+    }
+}
 
-        credits.cast.retain(|cast| !cast.character.is_empty() && !cast.name.is_empty());
-        credits.cast.truncate(20);
-        return Ok((credits.cast, credits.crew));
+/// Lowercases [title] and drops everything but letters and digits.
+fn normalize(title: &str) -> String {
+    title.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+fn group_releases(dates: ReleaseDates) -> Releases {
+    dates.results
+        .into_iter()
+        .map(|country| {
+            let releases = country.release_dates
+                .into_iter()
+                .map(|release| Release {
+                    kind: ReleaseType::from(release.kind),
+                    date: release.release_date,
+                    certification: Some(release.certification).filter(|text| !text.is_empty()),
+                    note: release.note.filter(|text| !text.is_empty()),
+                })
+                .collect();
+            (country.iso_3166_1, releases)
+        })
+        .collect()
+}
+
+/// Keeps the official trailers among [videos] in any of [languages], or all of them if [languages] is empty.
+fn official_trailers(videos: Vec<Video>, languages: &[String]) -> Vec<Video> {
+    videos.into_iter()
+        .filter(|video| video.official && video.kind == "Trailer")
+        .filter(|video| languages.is_empty() || languages.contains(&video.iso_639_1))
+        .collect()
+}
+
+fn localizations(translations: Translations) -> Localizations {
+    let text = |text: Option<String>| text.filter(|text| !text.is_empty());
+    translations.translations
+        .into_iter()
+        .map(|translation| (
+            format!("{}-{}", translation.iso_639_1, translation.iso_3166_1),
+            Localized {
+                title: text(translation.data.title),
+                overview: text(translation.data.overview),
+                tagline: text(translation.data.tagline),
+            },
+        ))
+        .collect()
+}
+
+fn group_titles(titles: AlternativeTitles) -> BTreeMap<String, Vec<String>> {
+    let mut grouped = BTreeMap::new();
+    for title in titles.titles {
+        grouped.entry(title.iso_3166_1).or_insert_with(Vec::new).push(title.title);
+    }
+    grouped
+}
+
+fn keyword_names(keywords: Keywords) -> Vec<String> {
+    keywords.keywords
+        .into_iter()
+        .map(|word| word.name)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, body: &str) -> Response {
+        Response { status, retry_after: None, body: body.as_bytes().to_vec() }
+    }
+
+    #[test]
+    fn errors_from_responses() {
+        let body = r#"{"status_code": 7, "status_message": "Invalid API key"}"#;
+        match TMDBError::from_response(&response(401, body)) {
+        | TMDBError::Unauthorized { message } => assert_eq!(message, "Invalid API key (code 7)"),
+        | error => panic!("unexpected {:?}", error),
+        }
+        match TMDBError::from_response(&response(503, "<html>")) {
+        | TMDBError::Server { status, message } => assert_eq!((status, message.as_str()), (503, "no error body (HTTP 503)")),
+        | error => panic!("unexpected {:?}", error),
+        }
+        match TMDBError::from_response(&response(404, body)) { | TMDBError::NotFound { .. } => (), | error => panic!("unexpected {:?}", error) }
+        match TMDBError::from_response(&response(429, body)) { | TMDBError::RateLimited { .. } => (), | error => panic!("unexpected {:?}", error) }
+        match TMDBError::from_response(&response(400, body)) { | TMDBError::Http { status: 400, .. } => (), | error => panic!("unexpected {:?}", error) }
+    }
+
+    fn release(kind: ReleaseType, certification: Option<&str>) -> Release {
+        Release {
+            kind,
+            date: "1994-09-23".to_owned(),
+            certification: certification.map(str::to_owned),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn us_certification_prefers_theatrical() {
+        let mut releases = Releases::new();
+        assert_eq!(us_certification(&releases), None);
+
+        releases.insert("GB".to_owned(), vec![release(ReleaseType::Theatrical, Some("15"))]);
+        assert_eq!(us_certification(&releases), None);
+
+        releases.insert("US".to_owned(), vec![
+            release(ReleaseType::Premiere, Some("NR")),
+            release(ReleaseType::Theatrical, None),
+            release(ReleaseType::TheatricalLimited, Some("R")),
+        ]);
+        assert_eq!(us_certification(&releases), Some("R".to_owned()));
+
+        releases.get_mut("US").unwrap().push(release(ReleaseType::Theatrical, Some("PG-13")));
+        assert_eq!(us_certification(&releases), Some("PG-13".to_owned()));
+
+        releases.insert("US".to_owned(), vec![release(ReleaseType::Digital, Some("PG"))]);
+        assert_eq!(us_certification(&releases), Some("PG".to_owned()));
+    }
+
+    #[test]
+    fn candidates_match_title_and_year() {
+        let candidate = Candidate {
+            id: 1,
+            title: "Ocean's Eleven".to_owned(),
+            original_title: None,
+            release_date: Some("2001-12-07".to_owned()),
+            popularity: 0.0,
+        };
+        assert!(candidate.matches("oceans eleven"));
+        assert!(!candidate.matches("Ocean's Twelve"));
+
+        assert!(candidate.released_near(None));
+        assert!(candidate.released_near(Some(2000)));
+        assert!(candidate.released_near(Some(2002)));
+        assert!(!candidate.released_near(Some(1960)));
+        assert!(!Candidate { release_date: None, ..candidate.clone() }.released_near(Some(2001)));
+        assert!(!Candidate { release_date: Some(String::new()), ..candidate }.released_near(Some(2001)));
     }
 }
@@ -0,0 +1,190 @@
+use failure::Error;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::fs::File;
+use serde_json;
+
+use tmdb::*;
+use imdb::*;
+use movie::*;
+
+#[derive(Deserialize, Serialize)]
+pub struct Episode {
+    pub tmdb_id: i32,
+    pub episode_number: i32,
+    pub name: String,
+    pub overview: Option<String>,
+    pub air_date: Option<String>,
+    pub runtime: Option<i32>,
+    pub still_path: Option<String>,
+    pub guest_stars: Vec<Cast>,
+    pub crew: Departments,
+    pub tmdb_score_value: f32,
+    pub tmdb_score_count: i32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Season {
+    pub tmdb_id: i32,
+    /// 0 for specials
+    pub season_number: i32,
+    pub name: String,
+    pub overview: Option<String>,
+    pub air_date: Option<String>,
+    pub poster_path: Option<String>,
+    pub episodes: Vec<Episode>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct TvShow {
+    /// IMDB ID, or `tmdb{id}` for shows IMDB doesn't list
+    pub id: String,
+    pub tmdb_id: i32,
+    pub cast: Vec<Cast>,
+    pub crew: Departments,
+    pub created_by: Vec<Creator>,
+    pub name: String,
+    pub original_name: Option<String>,
+    pub tagline: Option<String>,
+    pub genres: Vec<String>,
+    pub keywords: Vec<String>,
+    pub networks: Vec<Company>,
+    pub production_companies: Vec<Company>,
+    pub production_countries: Vec<Country>,
+    pub spoken_languages: Vec<SpokenLanguage>,
+    pub origin_country: Vec<String>,
+    pub original_language: String,
+    pub homepage: Option<String>,
+    pub rating: String,
+    /// Content rating by ISO 3166-1 country code
+    pub ratings: BTreeMap<String, String>,
+    pub status: Option<String>,
+    pub kind: Option<String>,
+    pub in_production: bool,
+    pub first_air_date: Option<String>,
+    pub last_air_date: Option<String>,
+    pub episode_run_time: Vec<i32>,
+    pub number_of_seasons: i32,
+    pub number_of_episodes: i32,
+    pub popularity: f32,
+    pub summary: String,
+    pub seasons: Vec<Season>,
+    pub tokens: Vec<String>,
+    pub tmdb_score_value: f32,
+    pub tmdb_score_count: i32,
+    pub imdb_score_value: f32,
+    pub imdb_score_count: i32,
+    pub meta_score_value: f32,
+    pub meta_score_count: i32,
+}
+
+impl TvShow {
+
+    /// Saves show [id] with every season into `shows/{id}.json`, scraping IMDB
+    /// for scores and the summary when TMDB knows the show's IMDB ID.
+    pub fn save(id: i32, tmdb: &TMDB, settings: &Settings) -> Result<Outcome, Error> {
+        let ShowBundle { show, imdb_id, cast, crew, keywords, ratings } = tmdb.get_show_bundle(id, &settings.credits)?;
+
+        if let Some(reason) = settings.policy.check_show(&show) {
+            return Ok(Outcome::Skipped(reason))
+        }
+
+        let imdb = match imdb_id {
+        | Some(ref imdb_id) => Some(IMDB::new(settings, imdb_id, &show.name)?),
+        | None => {
+            warn!("{} has no IMDB ID; using TMDB alone", show.name);
+            None
+        }
+        };
+        let key = imdb_id.unwrap_or_else(|| format!("tmdb{}", id));
+
+        let rating = match (ratings.get("US"), &imdb) {
+        | (Some(rating), _) => rating.clone(),
+        | (None, &Some(ref imdb)) => imdb
+            .get_rating()
+            .unwrap_or_else(|_| {
+                warn!("{} has no TV rating", show.name);
+                "NOT RATED".to_owned()
+            }),
+        | (None, &None) => "NOT RATED".to_owned(),
+        };
+
+        let ((imdb_score_value, imdb_score_count), (meta_score_value, meta_score_count), (summary, tokens)) = match imdb {
+        | Some(ref imdb) => (
+            imdb.get_imdb_score()?,
+            imdb.get_metacritic_score()
+                .unwrap_or_else(|_| {
+                    warn!("{} has no Metacritic ratings", show.name);
+                    (0., 0)
+                }),
+            describe(imdb, id, &show.name, &show.overview)?,
+        ),
+        | None => {
+            let summary = non_empty(show.overview.clone())
+                .ok_or_else(|| IMDBError::Summary { name: show.name.clone() })?;
+            let tokens = tokens(&summary);
+            ((0., 0), (0., 0), (summary, tokens))
+        }
+        };
+
+        let mut seasons = Vec::new();
+        for season in &show.seasons {
+            seasons.push(tmdb.get_season(id, season.season_number, &settings.credits)?);
+        }
+
+        if let Err(error) = settings.save_poster(imdb.as_ref(), &show.poster_path, &show.name, &key) {
+            warn!("{}: poster failed ({})", show.name, error);
+        }
+
+        let mut show_file = File::create(format!("shows/{}.json", key))?;
+        show_file.write_all(
+            serde_json::to_string(
+                &TvShow {
+                    id: key.clone(),
+                    tmdb_id: show.id,
+                    cast,
+                    crew,
+                    created_by: show.created_by,
+                    name: show.name.clone(),
+                    original_name: non_empty(show.original_name),
+                    tagline: non_empty(show.tagline),
+                    genres: show.genres.into_iter().map(|genre| genre.name).collect(),
+                    keywords,
+                    networks: show.networks,
+                    production_companies: show.production_companies,
+                    production_countries: show.production_countries,
+                    spoken_languages: show.spoken_languages,
+                    origin_country: show.origin_country,
+                    original_language: show.original_language,
+                    homepage: non_empty(show.homepage),
+                    rating,
+                    ratings,
+                    status: non_empty(show.status),
+                    kind: non_empty(show.kind),
+                    in_production: show.in_production,
+                    first_air_date: non_empty(show.first_air_date),
+                    last_air_date: non_empty(show.last_air_date),
+                    episode_run_time: show.episode_run_time,
+                    number_of_seasons: show.number_of_seasons,
+                    number_of_episodes: show.number_of_episodes,
+                    popularity: show.popularity,
+                    summary,
+                    seasons,
+                    tokens,
+                    tmdb_score_value: show.vote_average,
+                    tmdb_score_count: show.vote_count,
+                    imdb_score_value,
+                    imdb_score_count,
+                    meta_score_value,
+                    meta_score_count,
+                }
+            )?.as_bytes()
+        )?;
+
+        Ok(Outcome::Saved(Index {
+            id: key,
+            title: show.name,
+            tmdb_id: show.id,
+        }))
+    }
+}
@@ -0,0 +1,128 @@
+extern crate movies;
+extern crate serde_json;
+
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+
+use movies::fetch::Fixture;
+use movies::movie::{Movie, Outcome, Settings};
+use movies::retry::Backoff;
+use movies::tmdb::TMDB;
+
+const TMDB_BASE: &str = "http://tmdb.test/3";
+const IMDB_BASE: &str = "http://imdb.test";
+
+const BUNDLE: &str = r#"{
+    "id": 278,
+    "imdb_id": "tt0111161",
+    "title": "The Shawshank Redemption",
+    "original_title": "The Shawshank Redemption",
+    "tagline": "",
+    "adult": false,
+    "belongs_to_collection": null,
+    "budget": 25000000,
+    "genres": [{"id": 18, "name": "Drama"}],
+    "homepage": "",
+    "original_language": "en",
+    "overview": "Imprisoned in the 1940s for a double murder.",
+    "poster_path": "/shawshank.jpg",
+    "backdrop_path": null,
+    "popularity": 88.5,
+    "release_date": "1994-09-23",
+    "revenue": 28341469,
+    "runtime": 142,
+    "status": "Released",
+    "vote_average": 8.7,
+    "vote_count": 24000,
+    "credits": {
+        "cast": [
+            {"id": 504, "credit_id": "c1", "character": "Ellis Boyd 'Red' Redding", "name": "Morgan Freeman", "gender": 2, "order": 1, "profile_path": null},
+            {"id": 192, "credit_id": "c0", "character": "Andy Dufresne", "name": "Tim Robbins", "gender": 2, "order": 0, "profile_path": null}
+        ],
+        "crew": [
+            {"id": 4027, "credit_id": "c2", "department": "Directing", "job": "Director", "name": "Frank Darabont", "gender": 2, "profile_path": null},
+            {"id": 151, "credit_id": "c3", "department": "Camera", "job": "Director of Photography", "name": "Roger Deakins", "gender": 2, "profile_path": null}
+        ]
+    },
+    "keywords": {"keywords": [{"id": 378, "name": "prison"}]},
+    "release_dates": {"results": [
+        {"iso_3166_1": "US", "release_dates": [
+            {"certification": "R", "release_date": "1994-09-23T00:00:00.000Z", "type": 3, "note": ""}
+        ]}
+    ]}
+}"#;
+
+const HOME: &str = r#"<html><head>
+<script type="application/ld+json">{
+    "@type": "Movie",
+    "image": "http://imdb.test/shawshank@._V1_UX182.jpg",
+    "description": "Two imprisoned men bond over a number of years.",
+    "contentRating": "R",
+    "aggregateRating": {"ratingValue": 9.3, "ratingCount": "2,500,000"}
+}</script>
+</head><body></body></html>"#;
+
+/// Runs [Movie::save] against canned TMDB and IMDB responses, with no network access.
+#[test]
+fn save_from_fixtures() {
+    let mut fixture = Fixture::default();
+    fixture
+        .insert(
+            &format!(
+                "{}/movie/278?language=en-US&append_to_response=credits,keywords,release_dates&api_key=KEY",
+                TMDB_BASE,
+            ),
+            BUNDLE,
+        )
+        .insert(&format!("{}/title/tt0111161/", IMDB_BASE), HOME)
+        .insert("http://imdb.test/shawshank@._V1_.jpg", &b"poster"[..]);
+    let fixture = Arc::new(fixture);
+
+    let tmdb = TMDB::new("KEY")
+        .with_base_url(TMDB_BASE)
+        .with_fetcher(fixture.clone())
+        .with_retry(Backoff::none());
+
+    let settings = Settings {
+        fetcher: fixture,
+        imdb_base: IMDB_BASE.to_owned(),
+        imdb_retry: Backoff::none(),
+        poster_retry: Backoff::none(),
+        ..Settings::default()
+    };
+
+    // Movie::save writes into the working directory
+    let dir = env::temp_dir().join(format!("movies-save-{}", process::id()));
+    fs::create_dir_all(dir.join("movies")).unwrap();
+    fs::create_dir_all(dir.join("posters")).unwrap();
+    env::set_current_dir(&dir).unwrap();
+
+    match Movie::save(278, &tmdb, &settings).unwrap() {
+    | Outcome::Saved(index) => assert_eq!(index.id, "tt0111161"),
+    | Outcome::Skipped(reason) => panic!("skipped: {}", reason),
+    }
+
+    let movie: Movie = serde_json::from_reader(File::open("movies/tt0111161.json").unwrap()).unwrap();
+    assert_eq!(movie.tmdb_id, 278);
+    assert_eq!(movie.title, "The Shawshank Redemption");
+    assert_eq!(movie.tagline, None);
+    assert_eq!(movie.rating, "R");
+    assert_eq!(movie.summary, "Two imprisoned men bond over a number of years.");
+    assert_eq!((movie.imdb_score_value, movie.imdb_score_count), (9.3, 2_500_000));
+    assert_eq!((movie.meta_score_value, movie.meta_score_count), (0.0, 0));
+    assert_eq!(movie.keywords, vec!["prison"]);
+    assert_eq!(movie.cast.iter().map(|cast| cast.name.as_str()).collect::<Vec<_>>(), vec!["Tim Robbins", "Morgan Freeman"]);
+    assert_eq!(movie.crew.keys().collect::<Vec<_>>(), vec!["Directing"]);
+    assert!(!movie.tokens.is_empty());
+    assert_eq!(fs::read("posters/tt0111161.jpg").unwrap(), b"poster");
+
+    Movie::remove("tt0111161").unwrap();
+    assert_eq!(Movie::saved().unwrap().count(), 0);
+    assert!(!Path::new("posters/tt0111161.jpg").exists());
+
+    let _ = fs::remove_dir_all(&dir);
+}